@@ -0,0 +1,62 @@
+//! Cancellation of the operations via the JS `AbortSignal`.
+
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::{channel::oneshot, FutureExt as _};
+use wasm_bindgen::prelude::*;
+use web_sys::AbortSignal;
+
+/// `Future` which resolves once the provided `AbortSignal` is aborted.
+///
+/// Listener of the `abort` event is removed on drop.
+pub struct Aborted {
+    signal: AbortSignal,
+    closure: Closure<dyn FnMut()>,
+    rx: oneshot::Receiver<()>,
+}
+
+impl Aborted {
+    /// Subscribes to the `abort` event of the provided `AbortSignal`.
+    pub fn new(signal: AbortSignal) -> Self {
+        let (tx, rx) = oneshot::channel();
+        let mut tx = Some(tx);
+        let closure = Closure::wrap(Box::new(move || {
+            if let Some(tx) = tx.take() {
+                let _ = tx.send(());
+            }
+        }) as Box<dyn FnMut()>);
+        let _ = signal.add_event_listener_with_callback(
+            "abort",
+            closure.as_ref().unchecked_ref(),
+        );
+        Self {
+            signal,
+            closure,
+            rx,
+        }
+    }
+}
+
+impl Future for Aborted {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.signal.aborted() {
+            return Poll::Ready(());
+        }
+        self.rx.poll_unpin(cx).map(drop)
+    }
+}
+
+impl Drop for Aborted {
+    fn drop(&mut self) {
+        let _ = self.signal.remove_event_listener_with_callback(
+            "abort",
+            self.closure.as_ref().unchecked_ref(),
+        );
+    }
+}
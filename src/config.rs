@@ -0,0 +1,105 @@
+//! Configuration of the `RoomHandle`.
+
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    logger::{LogLevel, LogSink},
+    proto::Role,
+};
+
+/// Configuration of the `RoomHandle` provided on its construction.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct RoomConfig {
+    /// Maximum time in milliseconds to wait for server's confirmation of
+    /// mute.
+    pub mute_timeout: i32,
+
+    /// Maximum time in milliseconds to wait for server's confirmation of
+    /// unmute.
+    pub unmute_timeout: i32,
+
+    /// Maximum time in milliseconds to wait for each stage of the
+    /// `RoomHandle::connect`.
+    pub connect_timeout: i32,
+
+    /// Maximum number of times mute or unmute command is sent if it's not
+    /// confirmed in time (including the first one).
+    pub max_attempts: u32,
+
+    /// Delay in milliseconds before the first resend of the unconfirmed
+    /// command, doubled on each next one.
+    pub retry_backoff: i32,
+
+    /// Maximum number of the recent messages kept in the room's event log.
+    pub event_log_capacity: u32,
+
+    /// Interval in milliseconds between gatherings of the peers' statistics
+    /// returned by the `RoomHandle::get_stats`. Statistics aren't gathered
+    /// if it's not positive.
+    pub stats_interval: i32,
+
+    /// Indicates whether mute preference saved in the `localStorage` should
+    /// be re-applied on room creation. Has effect only with `persist`
+    /// feature enabled.
+    pub restore_mute: bool,
+
+    /// Indicates whether video should be muted while the page is hidden and
+    /// unmuted back once it's visible again (if it wasn't muted before).
+    pub auto_mute_hidden_video: bool,
+
+    /// Role requested on joining the room. Server may grant another one.
+    pub role: Role,
+
+    /// Way the mute is applied to the sent tracks. Has effect only with
+    /// `media` feature enabled.
+    pub mute_strategy: MuteStrategy,
+
+    /// Maximum level of the logged records.
+    pub log_level: LogLevel,
+
+    /// Destination of the logged records.
+    pub log_sink: LogSink,
+}
+
+/// Way the mute is applied to the sent `MediaStreamTrack`.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum MuteStrategy {
+    /// Track is disabled via `MediaStreamTrack.enabled`, so encoder keeps
+    /// running and unmute is instant.
+    #[default]
+    Enabled,
+
+    /// Track is detached via `RTCRtpSender.replaceTrack(null)`, so encoding
+    /// stops, saving CPU and battery.
+    ReplaceTrack,
+}
+
+#[wasm_bindgen]
+impl RoomConfig {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Default for RoomConfig {
+    fn default() -> Self {
+        Self {
+            mute_timeout: 4000,
+            unmute_timeout: 4000,
+            connect_timeout: 10000,
+            max_attempts: 3,
+            retry_backoff: 500,
+            event_log_capacity: 100,
+            stats_interval: 1000,
+            restore_mute: false,
+            auto_mute_hidden_video: false,
+            role: Role::Member,
+            mute_strategy: MuteStrategy::Enabled,
+            log_level: LogLevel::Warn,
+            log_sink: LogSink::Console,
+        }
+    }
+}
@@ -0,0 +1,122 @@
+//! Errors which `RoomHandle` promises reject with.
+
+use wasm_bindgen::prelude::*;
+
+use crate::{MediaDirection, MediaKinds};
+
+/// Kind of the [`RoomError`].
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RoomErrorKind {
+    /// Server didn't confirm operation in time.
+    Timeout,
+
+    /// Operation was superseded by the opposite one before confirmation.
+    Cancelled,
+
+    /// Connection with server is lost or can't be established.
+    Disconnected,
+
+    /// Server rejected the provided token.
+    Unauthorized,
+
+    /// Operation can't be performed while another one is in progress (e.g.
+    /// connecting to the room which is already being connected).
+    Busy,
+
+    /// Server rejected the operation.
+    ///
+    /// Reserved for the server's rejections of mute and unmute, which
+    /// aren't reported by the current protocol, so it's never produced yet.
+    ServerRejected,
+
+    /// Room was closed before operation completed.
+    Closed,
+
+    /// Track was removed before operation completed.
+    Dropped,
+
+    /// Operation failed for some of the media kinds, so it was rolled back
+    /// for the others.
+    RolledBack,
+
+    /// Operation isn't permitted for the user's role (e.g. unmuting media
+    /// muted by moderator).
+    PermissionDenied,
+}
+
+/// Error of the mute or unmute operation.
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct RoomError {
+    kind: RoomErrorKind,
+    message: String,
+    media: MediaKinds,
+    peer_ids: Vec<i32>,
+}
+
+impl RoomError {
+    /// Creates new [`RoomError`] affecting provided media kinds.
+    pub(crate) fn new<S: Into<String>>(
+        kind: RoomErrorKind,
+        message: S,
+        media: MediaKinds,
+    ) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            media,
+            peer_ids: Vec::new(),
+        }
+    }
+
+    /// Sets IDs of the peers which caused this [`RoomError`].
+    pub(crate) fn with_peers(mut self, peer_ids: Vec<i32>) -> Self {
+        self.peer_ids = peer_ids;
+        self
+    }
+}
+
+#[wasm_bindgen]
+impl RoomError {
+    #[wasm_bindgen(getter)]
+    pub fn kind(&self) -> RoomErrorKind {
+        self.kind
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn message(&self) -> String {
+        self.message.clone()
+    }
+
+    /// Indicates whether audio is affected by this error.
+    #[wasm_bindgen(getter)]
+    pub fn audio(&self) -> bool {
+        self.media.audio
+    }
+
+    /// Indicates whether video is affected by this error.
+    #[wasm_bindgen(getter)]
+    pub fn video(&self) -> bool {
+        self.media.video
+    }
+
+    /// Indicates whether screen sharing is affected by this error.
+    #[wasm_bindgen(getter)]
+    pub fn display(&self) -> bool {
+        self.media.display
+    }
+
+    /// IDs of the peers which caused this error, or empty array if it's not
+    /// caused by particular peers.
+    #[wasm_bindgen(getter)]
+    pub fn peer_ids(&self) -> Vec<i32> {
+        self.peer_ids.clone()
+    }
+
+    /// Direction of the media affected by this error.
+    #[wasm_bindgen(getter)]
+    pub fn direction(&self) -> MediaDirection {
+        self.media.direction
+    }
+}
@@ -0,0 +1,68 @@
+//! Log of the recent messages exchanged with server.
+
+use std::collections::VecDeque;
+
+use serde::Serialize;
+
+use crate::{
+    executor,
+    proto::{Command, Event},
+};
+
+/// Message exchanged with server.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Message {
+    /// [`Event`] received from server.
+    Event(Event),
+
+    /// [`Command`] sent to server.
+    Command(Command),
+}
+
+/// Single entry of the [`EventLog`].
+#[derive(Debug, Serialize)]
+pub struct LogEntry {
+    /// Time when the message was sent or received, in milliseconds since
+    /// the Unix epoch.
+    timestamp: f64,
+
+    #[serde(flatten)]
+    message: Message,
+}
+
+/// Bounded log of the recent messages, dropping the oldest ones once its
+/// capacity is reached.
+#[derive(Debug)]
+pub struct EventLog {
+    entries: VecDeque<LogEntry>,
+    capacity: usize,
+}
+
+impl EventLog {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Appends provided message to this log.
+    pub fn push(&mut self, message: Message) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(LogEntry {
+            timestamp: executor::now(),
+            message,
+        });
+    }
+
+    /// Returns all entries from the oldest to the newest.
+    pub fn entries(&self) -> impl Iterator<Item = &LogEntry> {
+        self.entries.iter()
+    }
+}
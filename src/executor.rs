@@ -0,0 +1,79 @@
+//! Platform-specific spawning of the background tasks and source of the
+//! current time, so the room logic runs both in a browser and natively
+//! (e.g. in plain `cargo test`).
+
+use std::future::Future;
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+    cell::RefCell,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+use futures::{
+    executor::{LocalPool, LocalSpawner},
+    task::LocalSpawnExt as _,
+};
+
+/// Spawns provided `Future` on the browser's event loop.
+#[cfg(target_arch = "wasm32")]
+pub fn spawn_local<F>(fut: F)
+where
+    F: Future<Output = ()> + 'static,
+{
+    wasm_bindgen_futures::spawn_local(fut);
+}
+
+/// Returns number of milliseconds elapsed since the Unix epoch.
+#[cfg(target_arch = "wasm32")]
+pub fn now() -> f64 {
+    js_sys::Date::now()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+thread_local! {
+    static POOL: RefCell<LocalPool> = RefCell::new(LocalPool::new());
+    static SPAWNER: LocalSpawner = POOL.with(|pool| pool.borrow().spawner());
+}
+
+/// Spawns provided `Future` on the current thread's pool, which is driven
+/// by the [`block_on`].
+#[cfg(not(target_arch = "wasm32"))]
+pub fn spawn_local<F>(fut: F)
+where
+    F: Future<Output = ()> + 'static,
+{
+    SPAWNER.with(|spawner| {
+        let _ = spawner.spawn_local(fut);
+    });
+}
+
+/// Runs provided `Future` to completion on the current thread, driving all
+/// the spawned tasks meanwhile.
+///
+/// # Panics
+///
+/// If called from within a `Future` driven by another [`block_on`].
+#[cfg(all(feature = "test-utils", not(target_arch = "wasm32")))]
+pub fn block_on<F: Future>(fut: F) -> F::Output {
+    POOL.with(|pool| pool.borrow_mut().run_until(fut))
+}
+
+/// Runs all the spawned tasks on the current thread until none of them can
+/// make progress anymore.
+///
+/// # Panics
+///
+/// If called from within a `Future` driven by the [`block_on`].
+#[cfg(all(feature = "test-utils", not(target_arch = "wasm32")))]
+pub fn run_until_stalled() {
+    POOL.with(|pool| pool.borrow_mut().run_until_stalled());
+}
+
+/// Returns number of milliseconds elapsed since the Unix epoch.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |elapsed| elapsed.as_secs_f64() * 1000.0)
+}
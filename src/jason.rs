@@ -0,0 +1,117 @@
+//! Entry point for joining multiple rooms over a single connection.
+
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use js_sys::Promise;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::future_to_promise;
+
+use crate::{
+    error::{RoomError, RoomErrorKind},
+    proto::RoomId,
+    timer::BrowserTimer,
+    ws::{RoomClient, WebSocket},
+    MediaKinds, RoomConfig, RoomHandle,
+};
+
+/// Root object owning connection with server, which is shared by all the
+/// joined rooms.
+#[wasm_bindgen]
+pub struct Jason {
+    ws: Rc<RefCell<WebSocket>>,
+
+    /// Indicates whether connection with server is established via
+    /// [`Jason::connect`].
+    is_connected: Rc<Cell<bool>>,
+}
+
+#[wasm_bindgen]
+impl Jason {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        console_error_panic_hook::set_once();
+        Self {
+            ws: Rc::new(RefCell::new(WebSocket::new())),
+            is_connected: Rc::new(Cell::new(false)),
+        }
+    }
+
+    /// Connects to the server with the provided URL, which connection is
+    /// shared by all the rooms joined afterwards.
+    ///
+    /// Rejects with [`RoomErrorKind::Disconnected`] error if connection
+    /// can't be established, or [`RoomErrorKind::Busy`] error if it's
+    /// already established.
+    #[wasm_bindgen(unchecked_return_type = "Promise<void>")]
+    pub fn connect(&self, url: String) -> Promise {
+        if self.is_connected.get() {
+            return Promise::reject(&error(
+                RoomErrorKind::Busy,
+                "Connection is already established",
+            ));
+        }
+        let connected = self.ws.borrow_mut().connect(&url);
+        let is_connected = Rc::clone(&self.is_connected);
+        future_to_promise(async move {
+            connected
+                .await
+                .map_err(|e| error(RoomErrorKind::Disconnected, &e))?;
+            is_connected.set(true);
+            Ok(JsValue::UNDEFINED)
+        })
+    }
+
+    /// Authorizes with the provided token and joins room with the provided
+    /// ID over the connection established via [`Jason::connect`], resolving
+    /// with [`RoomHandle`] to it once its initial state is received.
+    ///
+    /// Rejects with [`RoomErrorKind::Disconnected`] error if connection isn't
+    /// established, [`RoomErrorKind::Busy`] error if the room with the same
+    /// ID is already joined, or the same errors as [`RoomHandle::connect`].
+    #[wasm_bindgen(unchecked_return_type = "Promise<RoomHandle>")]
+    pub fn join_room(
+        &self,
+        room_id: String,
+        token: String,
+        config: Option<RoomConfig>,
+    ) -> Promise {
+        let room_id = RoomId(room_id);
+        if !self.is_connected.get() {
+            return Promise::reject(&error(
+                RoomErrorKind::Disconnected,
+                "Connection isn't established",
+            ));
+        }
+        if self.ws.borrow().has_room(&room_id) {
+            return Promise::reject(&error(
+                RoomErrorKind::Busy,
+                "Room with the same ID is already joined",
+            ));
+        }
+        let client = RoomClient::new(room_id, Rc::clone(&self.ws));
+        let room = RoomHandle::new_with_client(
+            client,
+            config.unwrap_or_default(),
+            Rc::new(BrowserTimer),
+        );
+        future_to_promise(async move {
+            room.join(token).await?;
+            Ok(room.into())
+        })
+    }
+}
+
+impl Default for Jason {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns [`RoomError`] of the provided kind, not related to any media, as
+/// `JsValue`.
+fn error(kind: RoomErrorKind, message: &str) -> JsValue {
+    RoomError::new(kind, message, MediaKinds::default()).into()
+}
@@ -1,19 +1,182 @@
 //! Implementation for mute functional.
 
+mod abort;
+mod config;
+mod error;
+mod event_log;
+mod executor;
+mod jason;
+mod logger;
+#[cfg(feature = "media")]
+mod media;
+#[cfg(feature = "media")]
+mod platform;
+#[cfg(feature = "persist")]
+mod preferences;
+#[cfg(feature = "test-utils")]
+pub mod proto;
+#[cfg(not(feature = "test-utils"))]
 mod proto;
+mod reactivity;
+mod stats;
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
+mod timer;
+mod typings;
+mod visibility;
 mod ws;
 
-use std::{cell::RefCell, collections::HashMap, future::Future, rc::Rc};
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    fmt,
+    future::Future,
+    rc::{Rc, Weak},
+};
 
-use futures::channel::oneshot;
+use futures::{
+    channel::{mpsc, oneshot},
+    future::{AbortHandle, Either, LocalBoxFuture, Shared},
+    FutureExt as _, Stream, StreamExt as _,
+};
 use js_sys::Promise;
 use proto::{Command, Event};
+use serde::Serialize;
 use wasm_bindgen::prelude::*;
-use wasm_bindgen_futures::{future_to_promise, JsFuture};
+use wasm_bindgen_futures::future_to_promise;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::AbortSignal;
 
-use crate::ws::WebSocket;
+use crate::{
+    abort::Aborted,
+    event_log::EventLog,
+    executor::spawn_local,
+    proto::{RoomId, SenderKind},
+    reactivity::{ReactiveField, When, WhenError},
+    stats::Stats,
+    timer::BrowserTimer,
+    visibility::VisibilityListener,
+    ws::{RoomClient, WebSocket},
+};
+
+pub use crate::{
+    config::{MuteStrategy, RoomConfig},
+    error::{RoomError, RoomErrorKind},
+    jason::Jason,
+    logger::{LogLevel, LogSink},
+    proto::{MediaDirection, Role},
+    timer::Timer,
+};
+
+#[cfg(feature = "test-utils")]
+pub use crate::{
+    timer::ManualTimer,
+    ws::{FakeServer, Faults},
+};
+
+#[cfg(all(feature = "test-utils", not(target_arch = "wasm32")))]
+pub use crate::executor::{block_on, run_until_stalled};
+
+/// Error of a `Future` not resolved in time.
+#[derive(Debug)]
+struct TimedOut {
+    /// Number of milliseconds waited for the `Future` in total.
+    waited_ms: i32,
+
+    /// Number of attempts to get the `Future` resolved.
+    attempts: u32,
+}
+
+impl fmt::Display for TimedOut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "timed out in {} ms", self.waited_ms)?;
+        if self.attempts > 1 {
+            write!(f, " after {} attempts", self.attempts)?;
+        }
+        Ok(())
+    }
+}
+
+/// Resolves provided `Future` or errors with [`TimedOut`] if it doesn't
+/// resolve in `timeout_ms` milliseconds of the provided [`Timer`].
+async fn future_with_timeout<F: Future>(
+    timer: &dyn Timer,
+    fut: F,
+    timeout_ms: i32,
+) -> Result<F::Output, TimedOut> {
+    let timeout = timer.delay(timeout_ms);
+    futures::pin_mut!(fut, timeout);
+    match futures::future::select(fut, timeout).await {
+        Either::Left((output, _)) => Ok(output),
+        Either::Right(_) => Err(TimedOut {
+            waited_ms: timeout_ms,
+            attempts: 1,
+        }),
+    }
+}
+
+/// Awaits provided confirmation of the sent [`Command`], resending it
+/// according to the [`RoomConfig`]'s retry policy if it's not confirmed in
+/// `timeout_ms` milliseconds.
+///
+/// [`Command`] is not resent if it's `None` (i.e. wasn't sent by the
+/// caller) or if the [`Room`] is closed.
+async fn confirm_with_retries<F: Future>(
+    room: &Rc<RefCell<Room>>,
+    confirmation: F,
+    cmd: Option<Command>,
+    timeout_ms: i32,
+) -> Result<F::Output, TimedOut> {
+    let (max_attempts, mut backoff_ms, timer) = {
+        let room = room.borrow();
+        let config = &room.config;
+        (
+            config.max_attempts,
+            config.retry_backoff,
+            Rc::clone(&room.timer),
+        )
+    };
+    futures::pin_mut!(confirmation);
+    let mut timed_out = TimedOut {
+        waited_ms: 0,
+        attempts: 0,
+    };
+    loop {
+        timed_out.attempts += 1;
+        match future_with_timeout(
+            timer.as_ref(),
+            confirmation.as_mut(),
+            timeout_ms,
+        )
+        .await
+        {
+            Ok(output) => return Ok(output),
+            Err(e) => timed_out.waited_ms += e.waited_ms,
+        }
+        let cmd = match &cmd {
+            Some(cmd) if timed_out.attempts < max_attempts => cmd,
+            _ => return Err(timed_out),
+        };
+        match future_with_timeout(
+            timer.as_ref(),
+            confirmation.as_mut(),
+            backoff_ms,
+        )
+        .await
+        {
+            Ok(output) => return Ok(output),
+            Err(e) => timed_out.waited_ms += e.waited_ms,
+        }
+        if room.borrow().is_closed {
+            return Err(timed_out);
+        }
+        room.borrow_mut().send(cmd.clone());
+        backoff_ms = backoff_ms.saturating_mul(2);
+    }
+}
 
 /// Resolves after provided number of milliseconds.
+#[cfg(target_arch = "wasm32")]
 pub async fn resolve_after(delay_ms: i32) -> Result<(), JsValue> {
     JsFuture::from(Promise::new(&mut |yes, _| {
         web_sys::window()
@@ -27,259 +190,5112 @@ pub async fn resolve_after(delay_ms: i32) -> Result<(), JsValue> {
     Ok(())
 }
 
-#[derive(Eq, PartialEq, Hash)]
+/// Resolves after provided number of milliseconds, which are waited for by
+/// a separate thread.
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn resolve_after(delay_ms: i32) -> Result<(), JsValue> {
+    let (tx, rx) = futures::channel::oneshot::channel();
+    let delay = std::time::Duration::from_millis(delay_ms.max(0) as u64);
+    std::thread::spawn(move || {
+        std::thread::sleep(delay);
+        let _ = tx.send(());
+    });
+    let _ = rx.await;
+    Ok(())
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
 struct PeerId(pub i32);
 
+/// Set of media kinds which an operation is applied to.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, Serialize)]
+struct MediaKinds {
+    audio: bool,
+    video: bool,
+    display: bool,
+    direction: MediaDirection,
+}
+
+impl MediaKinds {
+    /// Creates [`MediaKinds`] of the provided audio and/or video.
+    pub fn new(audio: bool, video: bool) -> Self {
+        Self {
+            audio,
+            video,
+            display: false,
+            direction: MediaDirection::Send,
+        }
+    }
+
+    /// Creates [`MediaKinds`] of all kinds in both directions.
+    pub fn all() -> Self {
+        Self {
+            audio: true,
+            video: true,
+            display: true,
+            direction: MediaDirection::Both,
+        }
+    }
+
+    /// Creates [`MediaKinds`] of the single provided kind and direction.
+    pub fn of(kind: SenderKind, direction: MediaDirection) -> Self {
+        Self {
+            audio: kind == SenderKind::Audio,
+            video: kind == SenderKind::Video,
+            display: kind == SenderKind::Display,
+            direction,
+        }
+    }
+
+    /// Creates [`MediaKinds`] of the screen sharing only.
+    pub fn display() -> Self {
+        Self {
+            display: true,
+            ..Self::default()
+        }
+    }
+
+    /// Returns these [`MediaKinds`] applied to the provided direction.
+    pub fn with_direction(self, direction: MediaDirection) -> Self {
+        Self { direction, ..self }
+    }
+
+    /// Adds kinds of the provided [`MediaKinds`] to these ones.
+    pub fn merge(&mut self, other: MediaKinds) {
+        self.audio |= other.audio;
+        self.video |= other.video;
+        self.display |= other.display;
+    }
+
+    /// Indicates whether no kinds are included.
+    pub fn is_empty(self) -> bool {
+        !(self.audio || self.video || self.display)
+    }
+
+    /// Indicates whether [`Track`] of the provided kind is included.
+    pub fn contains(self, kind: SenderKind) -> bool {
+        match kind {
+            SenderKind::Audio => self.audio,
+            SenderKind::Video => self.video,
+            SenderKind::Display => self.display,
+        }
+    }
+
+    /// Indicates whether this and the provided [`MediaKinds`] have common
+    /// kinds in common direction.
+    pub fn overlaps(self, other: MediaKinds) -> bool {
+        let kinds_overlap = (self.audio && other.audio)
+            || (self.video && other.video)
+            || (self.display && other.display);
+        kinds_overlap && self.direction.overlaps(other.direction)
+    }
+}
+
+impl fmt::Display for MediaKinds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kinds: Vec<_> = [
+            (self.audio, "audio"),
+            (self.video, "video"),
+            (self.display, "display"),
+        ]
+        .iter()
+        .filter(|(included, _)| *included)
+        .map(|(_, name)| *name)
+        .collect();
+        write!(f, "{}", kinds.join(" and "))
+    }
+}
+
+/// Kind of the operation performed on a [`Room`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum OperationKind {
+    Mute,
+    Unmute,
+}
+
+/// Peers which an operation performed on a [`Room`] is applied to.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum OperationTarget {
+    /// All peers of the [`Room`].
+    Room,
+
+    /// Peers selected by the operation with the provided unique ID, so it's
+    /// never joined by other operations.
+    Peers(u64),
+}
+
+/// Key identifying identical operations performed on a [`Room`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+struct OperationKey {
+    kind: OperationKind,
+    media: MediaKinds,
+    target: OperationTarget,
+}
+
+impl OperationKey {
+    /// Indicates whether operation with this key cancels the provided one,
+    /// i.e. they are opposite and affect the same kind of media.
+    pub fn supersedes(&self, other: &OperationKey) -> bool {
+        self.kind != other.kind && self.overlaps(other)
+    }
+
+    /// Indicates whether operations with this and the provided keys affect
+    /// the same kind of media.
+    pub fn overlaps(&self, other: &OperationKey) -> bool {
+        self.media.overlaps(other.media)
+    }
+}
+
+/// Mute configuration of the [`Room`]'s peers saved on hold, as mute flags
+/// of each [`Track`] along with IDs of the [`Sender`]s.
+type HoldSnapshot = HashMap<PeerId, Vec<(MediaKinds, Option<String>, bool)>>;
+
+/// `Future` of the operation performed on a [`Room`], which may be joined by
+/// multiple callers.
+type SharedOperation = Shared<LocalBoxFuture<'static, OperationResult>>;
+
+/// Result of the operation performed on a [`Room`].
+type OperationResult = Result<OperationReport, RoomError>;
+
+/// Operation queued or awaiting server's confirmation.
+struct PendingOperation {
+    /// Unique ID of this operation.
+    id: u64,
+
+    /// `Future` resolving once this operation is completed.
+    fut: SharedOperation,
+
+    /// Indicates whether this operation was superseded by a later one, so
+    /// shouldn't be started anymore.
+    is_collapsed: Rc<Cell<bool>>,
+
+    /// Number of callers awaiting this operation, which didn't detach from
+    /// it yet.
+    subscribers: Cell<usize>,
+}
+
 struct Room {
     peers: HashMap<PeerId, PeerConnection>,
-    ws: WebSocket,
+
+    /// Indicates whether initial peers are received from server.
+    is_initialized: ReactiveField<bool>,
+    ws: RoomClient,
+    config: RoomConfig,
+    optimistic_mute: bool,
+    is_closed: bool,
+    pending_ops: HashMap<OperationKey, PendingOperation>,
+    next_op_id: u64,
+    state: RoomState,
+    stats: Stats,
+    event_log: EventLog,
+    event_subs: Vec<mpsc::UnboundedSender<Event>>,
+    hold_snapshot: Option<HoldSnapshot>,
+    scheduled_mute: Option<AbortHandle>,
+    timer: Rc<dyn Timer>,
+    on_force_muted: Option<js_sys::Function>,
+    remote_mutes: HashMap<PeerId, RemoteMuteState>,
+    on_remote_mute_changed: Option<js_sys::Function>,
+
+    /// Listeners added via [`RoomHandle::add_event_listener`] by the event
+    /// names.
+    event_listeners: HashMap<String, Vec<js_sys::Function>>,
+    role: Role,
+
+    /// Sequence number of the latest applied [`Event`].
+    last_seq: Option<u64>,
+
+    /// Listener of the page visibility, if video should be muted while the
+    /// page is hidden.
+    visibility_listener: Option<VisibilityListener>,
+
+    /// Indicates whether video was muted before the page became hidden.
+    video_muted_before_hidden: Option<bool>,
+    #[cfg(feature = "media")]
+    on_track_replaced: Option<js_sys::Function>,
+
+    /// Sender of the [`RoomCall`]s to the task owning this [`Room`].
+    calls: mpsc::UnboundedSender<RoomCall>,
 }
 
 impl Room {
-    pub fn handle_event(&mut self, event: &Event) {
+    /// Applies provided [`Event`] with the provided sequence number.
+    ///
+    /// Events older than the latest applied one are discarded, so replayed
+    /// events can't revert the newer state.
+    pub fn handle_event(&mut self, seq: u64, event: &Event) {
+        if let Some(last_seq) = self.last_seq.filter(|last| seq <= *last) {
+            log::warn!(
+                "Discarding stale event #{} (latest applied is #{}): {:?}",
+                seq,
+                last_seq,
+                event,
+            );
+            return;
+        }
+        self.last_seq = Some(seq);
+        log::debug!("Handling event #{}: {:?}", seq, event);
+        self.event_log
+            .push(event_log::Message::Event(event.clone()));
+        self.event_subs
+            .retain(|tx| tx.unbounded_send(event.clone()).is_ok());
         match event {
-            Event::RoomMuted { video, audio } => {
-                self.peers.iter_mut().for_each(|(_, peer)| {
-                    peer.mute(*audio, *video);
-                });
+            Event::RoomMuted {
+                video,
+                audio,
+                display,
+                direction,
+            } => {
+                let media = MediaKinds {
+                    audio: *audio,
+                    video: *video,
+                    display: *display,
+                    direction: *direction,
+                };
+                let key = OperationKey {
+                    kind: OperationKind::Mute,
+                    media,
+                    target: OperationTarget::Room,
+                };
+                for (id, peer) in &mut self.peers {
+                    if peer.mute(media) {
+                        self.stats.record_transition(*id, key);
+                    }
+                }
+                self.stats.confirmed(key);
             }
-            Event::RoomUnmuted { video, audio } => {
-                self.peers.iter_mut().for_each(|(_, peer)| {
-                    peer.unmute(*audio, *video);
-                })
+            Event::RoomUnmuted {
+                video,
+                audio,
+                display,
+                direction,
+            } => {
+                let media = MediaKinds {
+                    audio: *audio,
+                    video: *video,
+                    display: *display,
+                    direction: *direction,
+                };
+                let key = OperationKey {
+                    kind: OperationKind::Unmute,
+                    media,
+                    target: OperationTarget::Room,
+                };
+                for (id, peer) in &mut self.peers {
+                    if peer.unmute(media) {
+                        self.stats.record_transition(*id, key);
+                    }
+                }
+                self.stats.confirmed(key);
+            }
+            // Confirm `RoomHandle::hold` and `RoomHandle::resume`, which
+            // await them via `event_subs`.
+            Event::RoomHeld | Event::RoomResumed => (),
+            Event::PeerMuted {
+                peer_id,
+                video,
+                audio,
+            } => {
+                if let Some(peer) = self.peers.get_mut(&PeerId(*peer_id)) {
+                    peer.mute(MediaKinds::new(*audio, *video));
+                }
+            }
+            Event::ForceMuted { video, audio } => {
+                let media = MediaKinds::new(*audio, *video);
+                self.peers
+                    .values_mut()
+                    .for_each(|peer| peer.force_mute(media));
+                if let Some(callback) = self.on_force_muted.clone() {
+                    let muted = ForceMuted {
+                        audio: *audio,
+                        video: *video,
+                    };
+                    match serde_wasm_bindgen::to_value(&muted) {
+                        Ok(muted) => spawn_local(async move {
+                            let _ = callback.call1(&JsValue::NULL, &muted);
+                        }),
+                        Err(e) => {
+                            log::error!("Failed to pass {:?}: {}", muted, e)
+                        }
+                    }
+                }
+            }
+            Event::SenderAdded {
+                peer_id,
+                track_id,
+                kind,
+            } => {
+                if let Some(peer) = self.peers.get_mut(&PeerId(*peer_id)) {
+                    peer.add_sender(*kind, track_id.clone());
+                }
+            }
+            Event::SenderRemoved { peer_id, track_id } => {
+                if let Some(peer) = self.peers.get_mut(&PeerId(*peer_id)) {
+                    peer.remove_sender(track_id);
+                }
+            }
+            Event::HelloAccepted | Event::HelloRejected { .. } => (),
+            Event::RoomJoined { role, peers } => {
+                self.role = *role;
+                self.state.connection.set(ConnectionState::Connected);
+                self.apply_snapshot(peers);
+            }
+            Event::TrackMuted { peer_id, track_id } => {
+                if let Some(sender) =
+                    self.sender_mut(PeerId(*peer_id), track_id)
+                {
+                    sender.mute();
+                }
+            }
+            Event::RoomState { peers } => self.apply_snapshot(peers),
+            Event::MemberMuted {
+                peer_id,
+                audio,
+                video,
+            } => {
+                let state = RemoteMuteState {
+                    peer_id: *peer_id,
+                    audio: *audio,
+                    video: *video,
+                };
+                let prev = self.remote_mutes.insert(PeerId(*peer_id), state);
+                let callback = self
+                    .on_remote_mute_changed
+                    .clone()
+                    .filter(|_| prev != Some(state));
+                if let Some(callback) = callback {
+                    match serde_wasm_bindgen::to_value(&state) {
+                        Ok(state) => spawn_local(async move {
+                            let _ = callback.call1(&JsValue::NULL, &state);
+                        }),
+                        Err(e) => {
+                            log::error!("Failed to pass {:?}: {}", state, e)
+                        }
+                    }
+                }
+            }
+            Event::ForceMuteReleased { video, audio } => {
+                let media = MediaKinds::new(*audio, *video);
+                self.peers
+                    .values_mut()
+                    .for_each(|peer| peer.release_force_mute(media));
             }
         }
+        self.update_state();
     }
 
-    fn is_busy(&self, audio: bool, video: bool) -> bool {
+    /// Reconciles peers and their [`Sender`]s with the provided server's
+    /// snapshot, applying mute state without sending any commands.
+    ///
+    /// Pending transitions, which the snapshot confirms, resolve, and ones,
+    /// which it contradicts, are cancelled.
+    ///
+    /// Completes initialization of the room, if it's not completed yet.
+    fn apply_snapshot(&mut self, peers: &[proto::PeerState]) {
         self.peers
-            .values()
-            .filter(|peer| peer.is_busy(audio, video))
-            .count()
-            != 0
+            .retain(|id, _| peers.iter().any(|p| p.peer_id == id.0));
+        let mute_strategy = self.config.mute_strategy;
+        for state in peers {
+            self.peers
+                .entry(PeerId(state.peer_id))
+                .or_insert_with(|| PeerConnection::new(mute_strategy))
+                .apply_snapshot(&state.senders);
+        }
+        self.is_initialized.set(true);
     }
-}
 
-#[wasm_bindgen]
-#[derive(Clone)]
-pub struct RoomHandle(Rc<RefCell<Room>>);
+    /// Attaches provided tracks to the [`Sender`]s of all peers.
+    #[cfg(feature = "media")]
+    fn set_tracks(&mut self, tracks: &[media::MediaStreamTrack]) {
+        for track in tracks {
+            self.replace_track(track);
+        }
+    }
 
-#[wasm_bindgen]
-impl RoomHandle {
-    #[wasm_bindgen(constructor)]
-    pub fn new() -> Self {
-        console_error_panic_hook::set_once();
-        let ws = WebSocket::new();
-        let mut peers = HashMap::new();
-        peers.insert(PeerId(100), PeerConnection::new());
-        let room = Rc::new(RefCell::new(Room { peers, ws }));
-        let room_clone = room.clone();
-        room.borrow_mut().ws.on_message(move |event| {
-            room_clone.borrow_mut().handle_event(&event);
-        });
-        Self(room)
+    /// Replaces tracks of the [`Sender`]s of the same kind as the provided
+    /// one, applying their mute state to it and stopping the replaced ones.
+    ///
+    /// Returns kind of the replaced [`Sender`]s and whether they are muted,
+    /// or `None` if track is of unsupported kind.
+    #[cfg(feature = "media")]
+    fn replace_track(
+        &mut self,
+        track: &media::MediaStreamTrack,
+    ) -> Option<(SenderKind, bool)> {
+        let kind = SenderKind::of_track(track)?;
+        let mut is_muted = false;
+        for peer in self.peers.values_mut() {
+            for sender in peer.tracks.iter_mut().filter(|s| s.kind == kind) {
+                is_muted |= sender.is_muted();
+                if let Some(old) = sender.replace_track(track.clone()) {
+                    old.stop();
+                }
+            }
+        }
+        Some((kind, is_muted))
+    }
+
+    /// Stops receiving events from server and drops all peers, so pending
+    /// operations are cancelled.
+    fn close(&mut self) {
+        self.is_closed = true;
+        if let Some(scheduled) = self.scheduled_mute.take() {
+            scheduled.abort();
+        }
+        self.visibility_listener = None;
+        self.is_initialized.drop_when_subs();
+        self.ws.close();
+        self.peers.clear();
+        self.state.connection.set(ConnectionState::Closed);
+        self.update_state();
+    }
+
+    /// Recalculates [`RoomState`] after peers or their [`Sender`]s change.
+    fn update_state(&mut self) {
+        let mute = RoomMuteState {
+            audio: self.mute_state(SenderKind::Audio),
+            video: self.mute_state(SenderKind::Video),
+            display: self.mute_state(SenderKind::Display),
+        };
+        self.state.mute.set(mute);
+        self.state.peer_count.set(self.peers.len());
+    }
+
+    fn mute_local(&mut self, media: MediaKinds) {
+        self.peers
+            .values_mut()
+            .for_each(|peer| peer.mute_local(media));
+        self.update_state();
+    }
+
+    fn unmute_local(&mut self, media: MediaKinds) {
+        self.peers
+            .values_mut()
+            .for_each(|peer| peer.unmute_local(media));
+        self.update_state();
+    }
+
+    fn start_screen_share(&mut self) {
+        self.peers
+            .values_mut()
+            .for_each(PeerConnection::add_display_sender);
+        self.update_state();
+    }
+
+    fn stop_screen_share(&mut self) {
+        self.peers
+            .values_mut()
+            .for_each(PeerConnection::remove_display_sender);
+        self.update_state();
+    }
+
+    /// Sends provided [`Command`] to server.
+    fn send(&mut self, cmd: Command) {
+        log::debug!("Sending command: {:?}", cmd);
+        self.event_log
+            .push(event_log::Message::Command(cmd.clone()));
+        self.ws.send(cmd);
+    }
+
+    /// Rolls back transition of the provided kinds which wasn't confirmed
+    /// in time for the [`Track`]s failed in the provided [`OperationReport`].
+    ///
+    /// Only transitions still owned by the operation with the provided ID
+    /// are rolled back, so ones taken over by a superseding operation are
+    /// left to it.
+    fn time_out(
+        &mut self,
+        media: MediaKinds,
+        op_id: u64,
+        kind: OperationKind,
+        report: &OperationReport,
+    ) {
+        let failed = report.failed_peers().into_iter().map(PeerId);
+        let key = OperationKey {
+            kind,
+            media,
+            target: OperationTarget::Room,
+        };
+        self.stats.timed_out(failed, key);
+        self.rollback(media, op_id);
     }
 
-    // TODO: Maybe add timeout for this `Promise`?
-    //       Also we can mute room without server's event if this promise is
-    //       timed out.
-    pub fn mute(&self, audio: bool, video: bool) -> Promise {
-        let is_room_busy = self.0.borrow().is_busy(audio, video);
-        let on_mute_fut: Vec<_> = self
-            .0
-            .borrow_mut()
+    /// Starts unmute of the [`Track`]s confirmed by the provided
+    /// [`OperationReport`] of the partially failed mute with the provided ID,
+    /// so the room isn't left half-muted.
+    ///
+    /// Returns [`MediaKinds`] which mute is rolled back, along with
+    /// confirmations of their unmute and the sent [`Command::UnmuteRoom`].
+    fn undo_mute(
+        &mut self,
+        media: MediaKinds,
+        op_id: u64,
+        report: &OperationReport,
+    ) -> (MediaKinds, Vec<When>, Command) {
+        let mut confirmed =
+            MediaKinds::default().with_direction(media.direction);
+        for track in &report.confirmed {
+            confirmed.merge(MediaKinds::of(track.kind, track.direction));
+        }
+        let on_unmute = self
             .peers
-            .iter_mut()
-            .filter(|(_, peer)| !peer.is_muted(audio, video))
-            .map(|(_, peer)| peer.on_mute(audio, video))
+            .values_mut()
+            .flat_map(|peer| peer.on_unmute(confirmed, op_id))
             .collect();
+        self.update_state();
+        let cmd = Command::UnmuteRoom {
+            audio: confirmed.audio,
+            video: confirmed.video,
+            display: confirmed.display,
+            direction: confirmed.direction,
+        };
+        self.send(cmd.clone());
+        (confirmed, on_unmute, cmd)
+    }
 
-        if !is_room_busy && on_mute_fut.len() > 0 {
-            self.0.borrow().ws.send(Command::MuteRoom { audio, video });
-        }
-        future_to_promise(async move {
-            futures::future::join_all(on_mute_fut).await;
-            Ok(JsValue::NULL)
-        })
+    /// Returns [`Sender`] of the provided peer with the provided track ID.
+    fn sender_mut(
+        &mut self,
+        peer_id: PeerId,
+        track_id: &str,
+    ) -> Option<&mut Sender> {
+        self.peers
+            .get_mut(&peer_id)?
+            .tracks
+            .iter_mut()
+            .find(|sender| sender.id == track_id)
+    }
+
+    /// Calls listeners of the event with the provided name (if any) with the
+    /// value returned by the provided function.
+    fn dispatch<F>(&self, name: &str, payload: F)
+    where
+        F: FnOnce() -> JsValue,
+    {
+        let listeners = match self.event_listeners.get(name) {
+            Some(listeners) if !listeners.is_empty() => listeners.clone(),
+            _ => return,
+        };
+        let payload = payload();
+        spawn_local(async move {
+            for listener in listeners {
+                let _ = listener.call1(&JsValue::NULL, &payload);
+            }
+        });
     }
 
-    pub fn unmute(&self, audio: bool, video: bool) -> Promise {
-        let is_room_busy = self.0.borrow().is_busy(audio, video);
-        let on_unmute_fut: Vec<_> = self
-            .0
-            .borrow_mut()
+    /// Returns [`TrackInfo`]s of all [`Sender`]s of the room.
+    fn tracks(&self) -> Vec<TrackInfo> {
+        let mut tracks: Vec<_> = self
             .peers
-            .iter_mut()
-            .filter(|(_, peer)| peer.is_muted(audio, video))
-            .map(|(_, peer)| peer.on_unmute(audio, video))
+            .iter()
+            .flat_map(|(id, peer)| {
+                peer.tracks.iter().map(move |sender| sender.info(*id))
+            })
+            .collect();
+        tracks.sort_by(|a, b| {
+            (a.peer_id, &a.track_id).cmp(&(b.peer_id, &b.track_id))
+        });
+        tracks
+    }
+
+    /// Returns [`RoomInfo`] describing the current state of this [`Room`].
+    fn info(&self) -> RoomInfo {
+        let mut peers: Vec<_> = self
+            .peers
+            .iter()
+            .map(|(id, peer)| {
+                let mut tracks: Vec<_> =
+                    peer.tracks.iter().map(|sender| sender.info(*id)).collect();
+                tracks.sort_by(|a, b| a.track_id.cmp(&b.track_id));
+                PeerInfo {
+                    peer_id: id.0,
+                    tracks,
+                }
+            })
             .collect();
+        peers.sort_by_key(|peer| peer.peer_id);
+        RoomInfo {
+            connection: *self.state.connection.get(),
+            role: self.role,
+            mute: self.state.mute.get().clone(),
+            peers,
+        }
+    }
 
-        if !is_room_busy && on_unmute_fut.len() > 0 {
-            self.0
-                .borrow()
-                .ws
-                .send(Command::UnmuteRoom { audio, video });
+    /// Returns [`RoomSnapshot`] of the current state of this [`Room`].
+    #[cfg(feature = "test-utils")]
+    fn snapshot(&self) -> RoomSnapshot {
+        let mut pending_ops: Vec<_> = self.pending_ops.iter().collect();
+        pending_ops.sort_by_key(|(_, op)| op.id);
+        let mut remote_mutes: Vec<_> =
+            self.remote_mutes.values().copied().collect();
+        remote_mutes.sort_by_key(|state| state.peer_id);
+        RoomSnapshot {
+            connection: *self.state.connection.get(),
+            role: self.role,
+            tracks: self.tracks(),
+            pending_ops: pending_ops
+                .into_iter()
+                .map(|(key, _)| (key.kind, key.media))
+                .collect(),
+            remote_mutes,
         }
-        future_to_promise(async move {
-            futures::future::join_all(on_unmute_fut).await;
-            Ok(JsValue::NULL)
+    }
+
+    /// Returns [`OperationReport`] of the [`Track`]s of the provided kinds,
+    /// considering ones in the `target` [`MuteState`] as confirmed.
+    fn report(&self, media: MediaKinds, target: MuteState) -> OperationReport {
+        let mut report = OperationReport::default();
+        for (id, peer) in &self.peers {
+            for track in peer.filter_tracks_by_kind(media) {
+                let outcome = TrackOutcome {
+                    peer_id: id.0,
+                    kind: track.kind(),
+                    direction: track.direction(),
+                };
+                if *track.state().get() == target {
+                    report.confirmed.push(outcome);
+                } else {
+                    report.failed.push(outcome);
+                }
+            }
+        }
+        report
+    }
+
+    /// Rolls back unconfirmed transitions of the provided kinds owned by the
+    /// operation with the provided ID.
+    fn rollback(&mut self, media: MediaKinds, op_id: u64) {
+        self.peers
+            .values_mut()
+            .for_each(|peer| peer.rollback(media, op_id));
+        self.update_state();
+    }
+
+    /// Returns [`AggregateMuteState`] of all [`Sender`]s of the provided kind.
+    fn mute_state(&self, kind: SenderKind) -> AggregateMuteState {
+        AggregateMuteState::from_states(
+            self.peers
+                .values()
+                .flat_map(|peer| peer.tracks.iter())
+                .filter(|sender| sender.kind == kind)
+                .map(|sender| *sender.state.get()),
+        )
+    }
+
+    /// Indicates whether all [`Track`]s of the provided kinds will be muted
+    /// once all pending operations are completed.
+    fn will_be_muted(&self, media: MediaKinds) -> bool {
+        let last_op = self
+            .pending_ops
+            .iter()
+            .filter(|(key, _)| {
+                key.target == OperationTarget::Room && key.media.overlaps(media)
+            })
+            .max_by_key(|(_, op)| op.id);
+        if let Some((key, _)) = last_op {
+            return key.kind == OperationKind::Mute;
+        }
+        self.peers.values().all(|peer| {
+            peer.filter_tracks_by_kind(media)
+                .all(|track| track.state().get().target().is_muted())
         })
     }
-}
 
-impl Default for RoomHandle {
-    fn default() -> Self {
-        Self::new()
+    /// Returns ID for a new operation.
+    fn next_op_id(&mut self) -> u64 {
+        let id = self.next_op_id;
+        self.next_op_id += 1;
+        id
     }
 }
 
-#[derive(Debug)]
-struct PeerConnection {
-    tracks: Vec<Sender>,
-}
+/// Call of a [`RoomHandle`] method performed by the task owning the
+/// [`Room`].
+type RoomCall = Box<dyn FnOnce(&RoomHandle)>;
 
-impl PeerConnection {
-    pub fn new() -> Self {
-        Self {
-            tracks: vec![
-                Sender::new(SenderKind::Video),
-                Sender::new(SenderKind::Audio),
-            ],
-        }
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct RoomHandle(Rc<RefCell<Room>>, mpsc::UnboundedSender<RoomCall>);
+
+#[wasm_bindgen]
+impl RoomHandle {
+    /// Creates new room with provided [`RoomConfig`] (or default one) and
+    /// its own connection with server, which should be established via
+    /// [`RoomHandle::connect`].
+    ///
+    /// Use [`Jason::join_room`] to share connection between multiple rooms.
+    #[wasm_bindgen(constructor)]
+    pub fn new(config: Option<RoomConfig>) -> Self {
+        console_error_panic_hook::set_once();
+        let ws = RoomClient::new(
+            RoomId("default".to_owned()),
+            Rc::new(RefCell::new(WebSocket::new())),
+        );
+        Self::new_with_client(
+            ws,
+            config.unwrap_or_default(),
+            Rc::new(BrowserTimer),
+        )
     }
 
-    pub fn filter_tracks_by_kind_mut(
-        &mut self,
+    /// Mutes provided kinds of media in the room.
+    ///
+    /// Resolves with `{ confirmed: object[], failed: object[] }` object
+    /// listing `{ peer_id, kind, direction }` of each affected track once
+    /// mute is confirmed by server. Mute is all-or-nothing: if only some
+    /// tracks aren't confirmed in time, the confirmed ones are unmuted back
+    /// and it rejects with [`RoomErrorKind::RolledBack`] error once server
+    /// confirms that unmute. Rejects with [`RoomErrorKind::Timeout`] error if
+    /// none of them is confirmed, or if unmute back isn't confirmed either.
+    ///
+    /// Only outgoing media is muted unless other `direction` is provided.
+    ///
+    /// If the provided `signal` is aborted before confirmation, it rejects
+    /// with [`RoomErrorKind::Cancelled`] error, and mute is superseded by
+    /// unmute unless other callers still await it.
+    #[wasm_bindgen(unchecked_return_type = "Promise<OperationReport>")]
+    pub fn mute(
+        &self,
         audio: bool,
         video: bool,
-    ) -> impl Iterator<Item = &mut Sender> {
-        self.tracks.iter_mut().filter(move |sender| {
-            (sender.kind == SenderKind::Audio && audio)
-                || (sender.kind == SenderKind::Video && video)
+        direction: Option<MediaDirection>,
+        signal: Option<AbortSignal>,
+    ) -> Promise {
+        let media = MediaKinds::new(audio, video)
+            .with_direction(direction.unwrap_or_default());
+        self.call_promise(media, move |this| {
+            let timeout = this.0.borrow().config.mute_timeout;
+            let key = OperationKey {
+                kind: OperationKind::Mute,
+                media,
+                target: OperationTarget::Room,
+            };
+            this.abortable(key, signal, || this.inner_mute(media, timeout))
         })
     }
 
-    pub fn filter_tracks_by_kind(
+    /// Mutes provided kinds of media in the room after `delay_ms`
+    /// milliseconds, cancelling previously scheduled mute (if any).
+    ///
+    /// Resolves once mute is confirmed by server or rejects with a
+    /// [`RoomError`], which is [`RoomErrorKind::Cancelled`] if mute was
+    /// cancelled before start.
+    #[wasm_bindgen(unchecked_return_type = "Promise<OperationReport>")]
+    pub fn mute_after(
         &self,
+        delay_ms: i32,
         audio: bool,
         video: bool,
-    ) -> impl Iterator<Item = &Sender> {
-        self.tracks.iter().filter(move |sender| {
-            (sender.kind == SenderKind::Audio && audio)
-                || (sender.kind == SenderKind::Video && video)
+    ) -> Promise {
+        let media = MediaKinds::new(audio, video);
+        self.call_promise(media, move |this| {
+            let mut room = this.0.borrow_mut();
+            let delay = room.timer.delay(delay_ms);
+            let (delay, abort) = futures::future::abortable(delay);
+            if let Some(prev) = room.scheduled_mute.replace(abort) {
+                prev.abort();
+            }
+            drop(room);
+
+            let this = this.clone();
+            future_to_promise(async move {
+                if delay.await.is_err() {
+                    return Err(RoomError::new(
+                        RoomErrorKind::Cancelled,
+                        "Scheduled mute was cancelled",
+                        media,
+                    )
+                    .into());
+                }
+                let timeout = {
+                    let mut room = this.0.borrow_mut();
+                    room.scheduled_mute = None;
+                    room.config.mute_timeout
+                };
+                let report = this.inner_mute(media, timeout).await?;
+                Ok(serde_wasm_bindgen::to_value(&report).unwrap())
+            })
         })
     }
 
-    pub fn mute(&mut self, audio: bool, video: bool) {
-        self.filter_tracks_by_kind_mut(audio, video)
-            .filter(|sender| !sender.is_muted())
-            .for_each(Sender::mute);
+    /// Cancels mute scheduled by [`RoomHandle::mute_after`] (if any).
+    pub fn cancel_scheduled_mute(&self) {
+        self.post(|this| {
+            if let Some(scheduled) = this.0.borrow_mut().scheduled_mute.take() {
+                scheduled.abort();
+            }
+        });
     }
 
-    pub fn unmute(&mut self, audio: bool, video: bool) {
-        self.filter_tracks_by_kind_mut(audio, video)
-            .filter(|sender| sender.is_muted())
-            .for_each(Sender::unmute);
+    /// Mutes screen sharing in the room.
+    ///
+    /// Resolves once mute is confirmed by server or rejects with a
+    /// [`RoomError`].
+    #[wasm_bindgen(unchecked_return_type = "Promise<OperationReport>")]
+    pub fn mute_display(&self) -> Promise {
+        let media = MediaKinds::display();
+        self.call_promise(media, move |this| {
+            let timeout = this.0.borrow().config.mute_timeout;
+            let fut = this.inner_mute(media, timeout);
+            future_to_promise(async move {
+                let report = fut.await?;
+                Ok(serde_wasm_bindgen::to_value(&report).unwrap())
+            })
+        })
     }
 
-    pub fn on_mute(
-        &mut self,
+    /// Same as [`RoomHandle::mute`], but waits for confirmation for the
+    /// provided number of milliseconds instead of [`RoomConfig`]'s one.
+    #[wasm_bindgen(unchecked_return_type = "Promise<OperationReport>")]
+    pub fn mute_with_timeout(
+        &self,
         audio: bool,
         video: bool,
-    ) -> impl Future<Output = Vec<Result<(), oneshot::Canceled>>> {
-        futures::future::join_all(
-            self.filter_tracks_by_kind_mut(audio, video)
-                .filter(|sender| !sender.is_muted())
-                .map(Sender::on_mute),
-        )
+        timeout_ms: i32,
+        direction: Option<MediaDirection>,
+    ) -> Promise {
+        let media = MediaKinds::new(audio, video)
+            .with_direction(direction.unwrap_or_default());
+        self.call_promise(media, move |this| {
+            let fut = this.inner_mute(media, timeout_ms);
+            future_to_promise(async move {
+                let report = fut.await?;
+                Ok(serde_wasm_bindgen::to_value(&report).unwrap())
+            })
+        })
     }
 
-    pub fn on_unmute(
-        &mut self,
+    /// Unmutes provided kinds of media in the room.
+    ///
+    /// Resolves once unmute is confirmed by server or rejects with a
+    /// [`RoomError`]. Resolves with the same object as [`RoomHandle::mute`].
+    ///
+    /// Only outgoing media is unmuted unless other `direction` is provided.
+    ///
+    /// If the provided `signal` is aborted before confirmation, it rejects
+    /// with [`RoomErrorKind::Cancelled`] error, and unmute is superseded by
+    /// mute unless other callers still await it.
+    #[wasm_bindgen(unchecked_return_type = "Promise<OperationReport>")]
+    pub fn unmute(
+        &self,
         audio: bool,
         video: bool,
-    ) -> impl Future<Output = Vec<Result<(), oneshot::Canceled>>> {
-        futures::future::join_all(
-            self.filter_tracks_by_kind_mut(audio, video)
-                .filter(|sender| sender.is_muted())
-                .map(Sender::on_unmute),
-        )
+        direction: Option<MediaDirection>,
+        signal: Option<AbortSignal>,
+    ) -> Promise {
+        let media = MediaKinds::new(audio, video)
+            .with_direction(direction.unwrap_or_default());
+        self.call_promise(media, move |this| {
+            let timeout = this.0.borrow().config.unmute_timeout;
+            let key = OperationKey {
+                kind: OperationKind::Unmute,
+                media,
+                target: OperationTarget::Room,
+            };
+            this.abortable(key, signal, || this.inner_unmute(media, timeout))
+        })
     }
 
-    pub fn is_busy(&self, audio: bool, video: bool) -> bool {
-        self.filter_tracks_by_kind(audio, video)
-            .filter(|sender| !sender.is_busy())
-            .count()
-            == 0
+    /// Unmutes screen sharing in the room.
+    ///
+    /// Resolves once unmute is confirmed by server or rejects with a
+    /// [`RoomError`].
+    #[wasm_bindgen(unchecked_return_type = "Promise<OperationReport>")]
+    pub fn unmute_display(&self) -> Promise {
+        let media = MediaKinds::display();
+        self.call_promise(media, move |this| {
+            let timeout = this.0.borrow().config.unmute_timeout;
+            let fut = this.inner_unmute(media, timeout);
+            future_to_promise(async move {
+                let report = fut.await?;
+                Ok(serde_wasm_bindgen::to_value(&report).unwrap())
+            })
+        })
     }
 
-    pub fn is_muted(&self, audio: bool, video: bool) -> bool {
-        self.filter_tracks_by_kind(audio, video)
-            .filter(|sender| !sender.is_muted())
-            .count()
-            == 0
+    /// Same as [`RoomHandle::unmute`], but waits for confirmation for the
+    /// provided number of milliseconds instead of [`RoomConfig`]'s one.
+    #[wasm_bindgen(unchecked_return_type = "Promise<OperationReport>")]
+    pub fn unmute_with_timeout(
+        &self,
+        audio: bool,
+        video: bool,
+        timeout_ms: i32,
+        direction: Option<MediaDirection>,
+    ) -> Promise {
+        let media = MediaKinds::new(audio, video)
+            .with_direction(direction.unwrap_or_default());
+        self.call_promise(media, move |this| {
+            let fut = this.inner_unmute(media, timeout_ms);
+            future_to_promise(async move {
+                let report = fut.await?;
+                Ok(serde_wasm_bindgen::to_value(&report).unwrap())
+            })
+        })
     }
-}
 
-#[derive(Debug, Eq, PartialEq)]
-enum SenderKind {
-    Video,
-    Audio,
-}
+    /// Mutes provided kinds of media if they are unmuted (or going to be
+    /// unmuted), and unmutes them otherwise.
+    ///
+    /// Pending operations are taken into account, so the room always lands
+    /// in the opposite of the state it would have without this call.
+    /// Resolves and rejects same as [`RoomHandle::mute`] or
+    /// [`RoomHandle::unmute`], including the provided `direction` and
+    /// `signal` handling.
+    #[wasm_bindgen(unchecked_return_type = "Promise<OperationReport>")]
+    pub fn toggle_mute(
+        &self,
+        audio: bool,
+        video: bool,
+        direction: Option<MediaDirection>,
+        signal: Option<AbortSignal>,
+    ) -> Promise {
+        let media = MediaKinds::new(audio, video)
+            .with_direction(direction.unwrap_or_default());
+        self.call_promise(media, move |this| {
+            let (will_be_muted, config) = {
+                let room = this.0.borrow();
+                (room.will_be_muted(media), room.config)
+            };
+            let key = OperationKey {
+                kind: if will_be_muted {
+                    OperationKind::Unmute
+                } else {
+                    OperationKind::Mute
+                },
+                media,
+                target: OperationTarget::Room,
+            };
+            this.abortable(key, signal, || match key.kind {
+                OperationKind::Mute => {
+                    this.inner_mute(media, config.mute_timeout)
+                }
+                OperationKind::Unmute => {
+                    this.inner_unmute(media, config.unmute_timeout)
+                }
+            })
+        })
+    }
 
-#[derive(Debug)]
-struct Sender {
-    kind: SenderKind,
-    on_mute: Vec<oneshot::Sender<()>>,
-    on_unmute: Vec<oneshot::Sender<()>>,
-    is_muted: bool,
-}
+    /// Returns aggregated mute state of the room as
+    /// `{ audio: string, video: string, display: string }` object, where each
+    /// value is one of `unmuted`, `muted`, `partially_muted` or
+    /// `in_progress`.
+    #[wasm_bindgen(unchecked_return_type = "RoomMuteState")]
+    pub fn mute_state(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(self.0.borrow().state.mute.get()).unwrap()
+    }
 
-impl Sender {
-    pub fn new(kind: SenderKind) -> Self {
-        Self {
-            kind,
-            on_mute: Vec::new(),
-            on_unmute: Vec::new(),
-            is_muted: false,
-        }
+    /// Indicates whether audio is muted for all peers of the room.
+    pub fn is_audio_muted(&self) -> bool {
+        self.0.borrow().state.mute.get().audio == AggregateMuteState::Muted
     }
 
-    pub fn mute(&mut self) {
-        self.is_muted = true;
-        self.on_mute
-            .drain(..)
-            .for_each(|on_mute| on_mute.send(()).unwrap());
+    /// Indicates whether video is muted for all peers of the room.
+    pub fn is_video_muted(&self) -> bool {
+        self.0.borrow().state.mute.get().video == AggregateMuteState::Muted
     }
 
-    pub fn unmute(&mut self) {
-        self.is_muted = false;
-        self.on_unmute
-            .drain(..)
-            .for_each(|on_unmute| on_unmute.send(()).unwrap());
+    /// Returns mute statistics of the room's peers as an array of
+    /// `{ peer_id, mutes, unmutes, timeouts, last_latency_ms,
+    /// avg_latency_ms }` objects.
+    ///
+    /// Statistics are gathered every [`RoomConfig::stats_interval`]
+    /// milliseconds, so the latest gathered ones are returned.
+    #[wasm_bindgen(unchecked_return_type = "PeerStats[]")]
+    pub fn get_stats(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(self.0.borrow().stats.collected()).unwrap()
     }
 
-    pub fn on_mute(
-        &mut self,
-    ) -> impl Future<Output = Result<(), oneshot::Canceled>> {
-        let (tx, rx) = oneshot::channel();
-        self.on_mute.push(tx);
-        rx
+    /// Returns recent messages exchanged with server as an array of
+    /// `{ timestamp, event }` and `{ timestamp, command }` objects, from the
+    /// oldest to the newest.
+    #[wasm_bindgen(unchecked_return_type = "LogEntry[]")]
+    pub fn event_log(&self) -> JsValue {
+        let room = self.0.borrow();
+        let entries: Vec<_> = room.event_log.entries().collect();
+        serde_wasm_bindgen::to_value(&entries).unwrap()
     }
 
-    pub fn on_unmute(
-        &mut self,
-    ) -> impl Future<Output = Result<(), oneshot::Canceled>> {
-        let (tx, rx) = oneshot::channel();
-        self.on_unmute.push(tx);
-        rx
+    /// Returns latencies of the confirmations of the mute and unmute
+    /// operations as `{ mute: object, unmute: object }` object, each being
+    /// `{ count, min_ms, max_ms, rolling_avg_ms, histogram }`, where
+    /// `histogram` counts operations confirmed in up to 100, 250, 500, 1000,
+    /// 2000, 4000 and more milliseconds.
+    #[wasm_bindgen(unchecked_return_type = "OperationLatencies")]
+    pub fn mute_latency_stats(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(self.0.borrow().stats.latencies()).unwrap()
+    }
+
+    /// Subscribes provided callback to the changes of the room's state.
+    ///
+    /// Callback is called with the
+    /// `{ connection: string, mute: object, peer_count: number }` object
+    /// (where `mute` is the same as returned by
+    /// [`RoomHandle::mute_state`]) every time any of its fields changes.
+    pub fn subscribe_state(
+        &self,
+        #[wasm_bindgen(
+            unchecked_param_type = "(state: RoomStateSnapshot) => void"
+        )]
+        callback: js_sys::Function,
+    ) {
+        self.post(move |this| {
+            let mut changes = this.0.borrow_mut().state.on_change();
+            let room = Rc::downgrade(&this.0);
+            spawn_local(async move {
+                while changes.next().await.is_some() {
+                    let room = match room.upgrade() {
+                        Some(room) => room,
+                        None => break,
+                    };
+                    let snapshot = room.borrow().state.snapshot();
+                    let snapshot =
+                        serde_wasm_bindgen::to_value(&snapshot).unwrap();
+                    let _ = callback.call1(&JsValue::NULL, &snapshot);
+                }
+            });
+        });
     }
 
-    pub fn is_busy(&self) -> bool {
-        self.on_mute.len() > 0 || self.on_unmute.len() > 0
+    /// Adds listener of the event with the provided name, following the
+    /// `EventTarget.addEventListener()` conventions:
+    /// - `mutestatechange` is called with the same object as returned by
+    ///   [`RoomHandle::mute_state`] when it changes;
+    /// - `connectionstatechange` is called with one of `new`, `connecting`,
+    ///   `connected` or `closed` when connection state changes;
+    /// - `error` is called with a [`RoomError`] when any mute or unmute
+    ///   operation (including [`TrackHandle::mute`], [`RoomHandle::hold`]
+    ///   and [`RoomHandle::resume`]) fails, is rejected before start or is
+    ///   aborted.
+    ///
+    /// Adding the same listener twice has no effect.
+    pub fn add_event_listener(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "RoomEventName")] name: String,
+        listener: js_sys::Function,
+    ) {
+        self.post(move |this| {
+            let mut room = this.0.borrow_mut();
+            let listeners = room.event_listeners.entry(name).or_default();
+            if !listeners.contains(&listener) {
+                listeners.push(listener);
+            }
+        });
+    }
+
+    /// Removes listener added via [`RoomHandle::add_event_listener`].
+    pub fn remove_event_listener(
+        &self,
+        name: String,
+        listener: js_sys::Function,
+    ) {
+        self.post(move |this| {
+            let mut room = this.0.borrow_mut();
+            if let Some(listeners) = room.event_listeners.get_mut(&name) {
+                listeners.retain(|added| *added != listener);
+            }
+        });
     }
 
-    pub fn is_muted(&self) -> bool {
-        self.is_muted
+    /// Subscribes provided callback to the raw events received by the room.
+    ///
+    /// Callback is called with the `{ command: string, data: object }`
+    /// object for every event. Returns function, which cancels this
+    /// subscription when called.
+    #[wasm_bindgen(unchecked_return_type = "() => void")]
+    pub fn subscribe_events(
+        &self,
+        #[wasm_bindgen(
+            unchecked_param_type = "(event: ProtocolMessage) => void"
+        )]
+        callback: js_sys::Function,
+    ) -> JsValue {
+        let (tx, mut rx) = mpsc::unbounded();
+        self.post(|this| this.0.borrow_mut().event_subs.push(tx));
+        let (fut, abort) = futures::future::abortable(async move {
+            while let Some(event) = rx.next().await {
+                let event = serde_wasm_bindgen::to_value(&event).unwrap();
+                let _ = callback.call1(&JsValue::NULL, &event);
+            }
+        });
+        spawn_local(fut.map(drop));
+        Closure::once_into_js(move || abort.abort())
+    }
+
+    /// Adds screen sharing [`Sender`] to all peers of the room.
+    pub fn start_screen_share(&self) {
+        self.post(|this| this.0.borrow_mut().start_screen_share());
+    }
+
+    /// Removes screen sharing [`Sender`] from all peers of the room, so
+    /// pending operations on it are cancelled.
+    pub fn stop_screen_share(&self) {
+        self.post(|this| this.0.borrow_mut().stop_screen_share());
+    }
+
+    /// Enables or disables optimistic mute mode.
+    ///
+    /// In this mode `mute()` disables tracks immediately instead of waiting
+    /// for server's confirmation, and rolls them back if confirmation times
+    /// out.
+    pub fn set_optimistic_mute(&self, enabled: bool) {
+        self.post(move |this| this.0.borrow_mut().optimistic_mute = enabled);
+    }
+
+    /// Mutes provided kinds of media right away, without notifying server
+    /// and waiting for its confirmation.
+    ///
+    /// Pending unmute of these kinds (if any) is cancelled.
+    pub fn mute_local(&self, audio: bool, video: bool) {
+        let media = MediaKinds::new(audio, video);
+        self.post(move |this| this.0.borrow_mut().mute_local(media));
+    }
+
+    /// Unmutes provided kinds of media right away, without notifying server
+    /// and waiting for its confirmation.
+    ///
+    /// Pending mute of these kinds (if any) is cancelled.
+    pub fn unmute_local(&self, audio: bool, video: bool) {
+        let media = MediaKinds::new(audio, video);
+        self.post(move |this| this.0.borrow_mut().unmute_local(media));
+    }
+
+    /// Puts the room on hold: mutes all media in both directions, remembering
+    /// mute state of each track to restore it on [`RoomHandle::resume`].
+    ///
+    /// Resolves once both mute and hold are confirmed by server or rejects
+    /// with a [`RoomError`], in which case the room isn't put on hold (though
+    /// media stays muted if only hold isn't confirmed). Does nothing if the
+    /// room is already on hold.
+    #[wasm_bindgen(unchecked_return_type = "Promise<OperationReport | null>")]
+    pub fn hold(&self) -> Promise {
+        self.call_promise(MediaKinds::all(), |this| {
+            let fut = this.inner_hold();
+            future_to_promise(async move {
+                Ok(match fut.await? {
+                    Some(report) => {
+                        serde_wasm_bindgen::to_value(&report).unwrap()
+                    }
+                    None => JsValue::NULL,
+                })
+            })
+        })
+    }
+
+    /// Resumes the room put on hold, restoring mute state of each track
+    /// remembered by [`RoomHandle::hold`].
+    ///
+    /// Resolves once unmute and resume are confirmed by server and tracks,
+    /// which were muted before hold, are muted back. Rejects with a
+    /// [`RoomError`] otherwise, in which case the room stays on hold (unless
+    /// only muting back fails), so resume may be retried. Does nothing if the
+    /// room is not on hold.
+    ///
+    /// Incoming media muted only for some peers before hold stays unmuted,
+    /// as it can't be muted back for a single peer.
+    #[wasm_bindgen(unchecked_return_type = "Promise<null>")]
+    pub fn resume(&self) -> Promise {
+        self.call_promise(MediaKinds::all(), |this| {
+            let fut = this.inner_resume();
+            future_to_promise(async move {
+                fut.await?;
+                Ok(JsValue::NULL)
+            })
+        })
+    }
+
+    /// Mutes audio and video of all peers of the room except the provided
+    /// ones.
+    ///
+    /// Resolves with `{ muted: number[], failed: number[] }` object listing
+    /// IDs of the peers which mute was confirmed by server and which was not.
+    /// Mute of the failed peers is rolled back.
+    ///
+    /// Rejects with [`RoomErrorKind::PermissionDenied`] unless the user is
+    /// [`Role::Moderator`], or with other [`RoomError`] if mute is superseded
+    /// before start or the room is closed.
+    #[wasm_bindgen(unchecked_return_type = "Promise<PeersMuteReport>")]
+    pub fn mute_all_except(&self, peer_ids: Vec<i32>) -> Promise {
+        let media = MediaKinds::new(true, true);
+        self.call_promise(media, move |this| {
+            let fut = this.inner_mute_all_except(peer_ids);
+            future_to_promise(async move {
+                let report = PeersMuteReport::from(fut.await?);
+                Ok(serde_wasm_bindgen::to_value(&report).unwrap())
+            })
+        })
+    }
+    /// Sets callback, which is called with `{ audio, video }` object when
+    /// media is muted by moderator.
+    ///
+    /// Such media can't be unmuted by [`Role::Member`] until server allows
+    /// it.
+    pub fn on_force_muted(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "(media: ForceMuted) => void")]
+        callback: js_sys::Function,
+    ) {
+        self.post(|this| this.0.borrow_mut().on_force_muted = Some(callback));
+    }
+
+    /// Sets callback, which is called with `{ peer_id, audio, video }` object
+    /// when mute state of a remote participant changes.
+    pub fn on_remote_mute_changed(
+        &self,
+        #[wasm_bindgen(
+            unchecked_param_type = "(state: RemoteMuteState) => void"
+        )]
+        callback: js_sys::Function,
+    ) {
+        self.post(|this| {
+            this.0.borrow_mut().on_remote_mute_changed = Some(callback);
+        });
+    }
+
+    /// Returns `{ peer_id, audio, video }` objects of all the remote
+    /// participants, whose mute state is known.
+    #[wasm_bindgen(unchecked_return_type = "RemoteMuteState[]")]
+    pub fn remote_mutes(&self) -> JsValue {
+        let room = self.0.borrow();
+        let mut mutes: Vec<_> = room.remote_mutes.values().collect();
+        mutes.sort_by_key(|state| state.peer_id);
+        serde_wasm_bindgen::to_value(&mutes).unwrap()
+    }
+
+    /// Returns `{ track_id, kind, muted, busy, peer_id }` objects of all the
+    /// outgoing tracks of the room.
+    #[wasm_bindgen(unchecked_return_type = "TrackInfo[]")]
+    pub fn tracks(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.0.borrow().tracks()).unwrap()
+    }
+
+    /// Returns current state of the room as
+    /// `{ connection: string, role: string, mute: object, peers: object[] }`
+    /// object, where `mute` is the same as returned by
+    /// [`RoomHandle::mute_state`] and each of `peers` is
+    /// `{ peer_id: number, tracks: object[] }` with the same tracks as
+    /// returned by [`RoomHandle::tracks`].
+    #[wasm_bindgen(unchecked_return_type = "RoomInfo")]
+    pub fn to_json(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.0.borrow().info()).unwrap()
+    }
+
+    /// Returns [`TrackHandle`] of the outgoing track with the provided ID of
+    /// the provided peer, or `undefined` if there is no such track.
+    pub fn track(&self, peer_id: i32, track_id: String) -> Option<TrackHandle> {
+        self.0
+            .borrow()
+            .peers
+            .get(&PeerId(peer_id))?
+            .tracks
+            .iter()
+            .find(|sender| sender.id == track_id)?;
+        Some(TrackHandle {
+            room: Rc::downgrade(&self.0),
+            peer_id: PeerId(peer_id),
+            track_id,
+        })
+    }
+
+    /// Sets callback, which is called with `(level, message)` for each
+    /// logged record when [`LogSink::Callback`] is configured.
+    pub fn on_log(
+        &self,
+        #[wasm_bindgen(
+            unchecked_param_type = "(level: string, message: string) => void"
+        )]
+        callback: js_sys::Function,
+    ) {
+        logger::set_callback(callback);
+    }
+
+    /// Returns records logged since the previous call when
+    /// [`LogSink::Buffered`] is configured.
+    pub fn take_logs(&self) -> Vec<String> {
+        logger::take_buffered()
+    }
+
+    /// Connects to the server with the provided URL, authorizes with the
+    /// provided token and joins the room.
+    ///
+    /// Resolves once the initial state of the room is received. Rejects
+    /// with [`RoomErrorKind::Disconnected`] error if connection can't be
+    /// established, [`RoomErrorKind::Unauthorized`] error if token is
+    /// rejected, [`RoomErrorKind::Timeout`] error if any stage isn't
+    /// completed in [`RoomConfig::connect_timeout`], or
+    /// [`RoomErrorKind::Busy`] error if the room is already connecting or
+    /// connected.
+    ///
+    /// Operations started before the room is joined are rejected with
+    /// [`RoomErrorKind::Disconnected`] error if connection fails.
+    #[wasm_bindgen(unchecked_return_type = "Promise<void>")]
+    pub fn connect(&self, url: String, token: String) -> Promise {
+        self.call_promise(MediaKinds::default(), |this| {
+            let this = this.clone();
+            future_to_promise(async move {
+                this.inner_connect(Some(&url), token).await?;
+                Ok(JsValue::UNDEFINED)
+            })
+        })
+    }
+
+    /// Returns role of the user in the room.
+    pub fn role(&self) -> Role {
+        self.0.borrow().role
+    }
+
+    /// Indicates whether the room is on hold.
+    pub fn is_on_hold(&self) -> bool {
+        self.0.borrow().hold_snapshot.is_some()
+    }
+
+    /// Closes the room: stops receiving server events, drops all peers and
+    /// rejects all pending operations with [`RoomErrorKind::Closed`].
+    pub fn close(&self) {
+        self.post(|this| this.0.borrow_mut().close());
+    }
+
+    /// Requests local camera and/or microphone and attaches acquired tracks
+    /// to the room's senders once the room is initialized.
+    ///
+    /// Rejects with a JS `Error` whose `name` is one of `PermissionDenied`,
+    /// `NotFound`, `Unsupported` or `MediaError`.
+    #[cfg(feature = "media")]
+    pub fn init_local_media(&self, audio: bool, video: bool) -> Promise {
+        let media = MediaKinds::new(audio, video);
+        self.call_promise(media, move |this| {
+            let room = Rc::clone(&this.0);
+            let initialized = room.borrow_mut().is_initialized.when_eq(true);
+            future_to_promise(async move {
+                let tracks = media::get_user_media(audio, video).await?;
+                let _ = initialized.await;
+                room.borrow_mut().set_tracks(&tracks);
+                Ok(JsValue::NULL)
+            })
+        })
+    }
+
+    /// Replaces local track of the same kind with the provided one (e.g. on
+    /// switching microphone), preserving its mute state.
+    ///
+    /// Callback set by [`RoomHandle::on_track_replaced`] is called with
+    /// `{ kind: string, muted: boolean }` object afterwards.
+    #[cfg(feature = "media")]
+    pub fn replace_track(
+        &self,
+        track: media::MediaStreamTrack,
+    ) -> Result<(), JsValue> {
+        SenderKind::of_track(&track)
+            .ok_or_else(|| js_sys::Error::new("Unsupported track kind"))?;
+        self.post(move |this| {
+            let mut room = this.0.borrow_mut();
+            let (kind, muted) = match room.replace_track(&track) {
+                Some(replaced) => replaced,
+                None => return,
+            };
+            if let Some(callback) = room.on_track_replaced.clone() {
+                let replaced = serde_wasm_bindgen::to_value(&TrackReplaced {
+                    kind,
+                    muted,
+                })
+                .unwrap();
+                spawn_local(async move {
+                    let _ = callback.call1(&JsValue::NULL, &replaced);
+                });
+            }
+        });
+        Ok(())
+    }
+
+    /// Sets callback, which is called when local track is replaced via
+    /// [`RoomHandle::replace_track`].
+    #[cfg(feature = "media")]
+    pub fn on_track_replaced(
+        &self,
+        #[wasm_bindgen(
+            unchecked_param_type = "(replaced: TrackReplaced) => void"
+        )]
+        callback: js_sys::Function,
+    ) {
+        self.post(|this| {
+            this.0.borrow_mut().on_track_replaced = Some(callback)
+        });
+    }
+}
+
+impl RoomHandle {
+    /// Creates new room with its own connection with server, which timeouts
+    /// and delays (including ones of the loopback server) are driven by the
+    /// provided [`Timer`].
+    ///
+    /// Connection should be established via [`RoomHandle::connect`].
+    pub fn with_timer(config: RoomConfig, timer: Rc<dyn Timer>) -> Self {
+        let ws = RoomClient::new(
+            RoomId("default".to_owned()),
+            Rc::new(RefCell::new(WebSocket::with_timer(Rc::clone(&timer)))),
+        );
+        Self::new_with_client(ws, config, timer)
+    }
+
+    /// Returns [`RoomSnapshot`] of the current state of this room, which
+    /// `Debug` representation can be compared against the expected one.
+    #[cfg(feature = "test-utils")]
+    pub fn snapshot(&self) -> RoomSnapshot {
+        self.0.borrow().snapshot()
+    }
+
+    /// Returns number of the peers of this room.
+    #[cfg(feature = "test-utils")]
+    pub fn peer_count(&self) -> usize {
+        self.0.borrow().peers.len()
+    }
+
+    /// Returns number of the mute and unmute operations in progress.
+    #[cfg(feature = "test-utils")]
+    pub fn pending_operations(&self) -> usize {
+        self.0.borrow().pending_ops.len()
+    }
+
+    /// Returns [`MuteState`] of the first outgoing track of the provided
+    /// kind of the provided peer, or `None` if there is no such track.
+    #[cfg(feature = "test-utils")]
+    pub fn sender_state(
+        &self,
+        peer_id: i32,
+        kind: SenderKind,
+    ) -> Option<MuteState> {
+        let room = self.0.borrow();
+        room.peers
+            .get(&PeerId(peer_id))?
+            .tracks
+            .iter()
+            .find(|sender| sender.kind == kind)
+            .map(|sender| *sender.state.get())
+    }
+
+    /// Creates new room with its own connection with the loopback server,
+    /// which failures are injected via the returned [`Faults`].
+    ///
+    /// Connection should be established via [`RoomHandle::connect`].
+    #[cfg(feature = "test-utils")]
+    pub fn with_faults(
+        config: RoomConfig,
+        timer: Rc<dyn Timer>,
+    ) -> (Self, Faults) {
+        let ws = RoomClient::new(
+            RoomId("default".to_owned()),
+            Rc::new(RefCell::new(WebSocket::with_timer(Rc::clone(&timer)))),
+        );
+        let faults = ws.faults();
+        (Self::new_with_client(ws, config, timer), faults)
+    }
+
+    /// Creates new room connected to the returned [`FakeServer`], which
+    /// timeouts and delays are driven by the provided [`Timer`].
+    ///
+    /// Room is joined once [`Event::RoomJoined`] is pushed via the
+    /// [`FakeServer::send`].
+    #[cfg(feature = "test-utils")]
+    pub fn with_fake_server(
+        config: RoomConfig,
+        timer: Rc<dyn Timer>,
+    ) -> (Self, FakeServer) {
+        let (server, client) = FakeServer::new(Rc::clone(&timer));
+        (Self::new_with_client(client, config, timer), server)
+    }
+
+    /// Creates new room communicating with server via provided
+    /// [`RoomClient`].
+    pub(crate) fn new_with_client(
+        ws: RoomClient,
+        config: RoomConfig,
+        timer: Rc<dyn Timer>,
+    ) -> Self {
+        logger::init(config.log_level, config.log_sink);
+        let (calls_tx, calls_rx) = mpsc::unbounded();
+        let room = Rc::new(RefCell::new(Room {
+            peers: HashMap::new(),
+            is_initialized: ReactiveField::new(false),
+            ws,
+            config,
+            optimistic_mute: false,
+            is_closed: false,
+            pending_ops: HashMap::new(),
+            next_op_id: 0,
+            state: RoomState::new(),
+            stats: Stats::default(),
+            event_log: EventLog::new(config.event_log_capacity as usize),
+            event_subs: Vec::new(),
+            hold_snapshot: None,
+            scheduled_mute: None,
+            timer: Rc::clone(&timer),
+            on_force_muted: None,
+            remote_mutes: HashMap::new(),
+            on_remote_mute_changed: None,
+            event_listeners: HashMap::new(),
+            role: config.role,
+            last_seq: None,
+            visibility_listener: None,
+            video_muted_before_hidden: None,
+            #[cfg(feature = "media")]
+            on_track_replaced: None,
+            calls: calls_tx.clone(),
+        }));
+        room.borrow_mut().update_state();
+
+        // Events and calls of `RoomHandle` methods are queued and handled
+        // one by one by a separate task owning the room, so none of them
+        // runs while the room is borrowed by another one.
+        let (events_tx, events_rx) = mpsc::unbounded();
+        room.borrow_mut().ws.on_message(move |msg| {
+            let _ = events_tx.unbounded_send(msg);
+        });
+        let weak_room = Rc::downgrade(&room);
+        spawn_local(async move {
+            let mut queue = futures::stream::select(
+                events_rx.map(Either::Left),
+                calls_rx.map(Either::Right),
+            );
+            while let Some(msg) = queue.next().await {
+                let room = match weak_room.upgrade() {
+                    Some(room) => room,
+                    None => break,
+                };
+                match msg {
+                    Either::Left(msg) => {
+                        room.borrow_mut().handle_event(msg.seq, &msg.event)
+                    }
+                    Either::Right(call) => {
+                        let calls = room.borrow().calls.clone();
+                        call(&Self(Rc::clone(&room), calls));
+                    }
+                }
+                if room.borrow().is_closed {
+                    break;
+                }
+            }
+        });
+        let this = Self(room, calls_tx);
+
+        let mute_changes = this.0.borrow_mut().state.mute.on_change();
+        this.dispatch_changes(mute_changes, "mutestatechange", |room| {
+            serde_wasm_bindgen::to_value(room.state.mute.get()).unwrap()
+        });
+        let connection_changes =
+            this.0.borrow_mut().state.connection.on_change();
+        this.dispatch_changes(
+            connection_changes,
+            "connectionstatechange",
+            |room| {
+                serde_wasm_bindgen::to_value(room.state.connection.get())
+                    .unwrap()
+            },
+        );
+
+        if config.stats_interval > 0 {
+            let weak_room = Rc::downgrade(&this.0);
+            spawn_local(async move {
+                loop {
+                    timer.delay(config.stats_interval).await;
+                    let room = match weak_room.upgrade() {
+                        Some(room) => room,
+                        None => break,
+                    };
+                    let mut room = room.borrow_mut();
+                    if room.is_closed {
+                        break;
+                    }
+                    let peers: Vec<_> = room.peers.keys().copied().collect();
+                    room.stats.collect(peers);
+                }
+            });
+        }
+
+        if config.auto_mute_hidden_video {
+            let calls = this.1.clone();
+            let listener = VisibilityListener::new(move |is_hidden| {
+                let _ = calls.unbounded_send(Box::new(move |this| {
+                    this.on_visibility_change(is_hidden)
+                }));
+            });
+            this.0.borrow_mut().visibility_listener = listener;
+        }
+
+        #[cfg(feature = "persist")]
+        if config.restore_mute {
+            if let Some(preference) = preferences::load() {
+                let media = MediaKinds::new(preference.audio, preference.video);
+                if !media.is_empty() {
+                    let fut = this.inner_mute(media, config.mute_timeout);
+                    spawn_local(fut.map(drop));
+                }
+            }
+        }
+
+        this
+    }
+
+    /// Authorizes with the provided token and joins the room over already
+    /// established connection, same as [`RoomHandle::connect`] does.
+    pub(crate) async fn join(&self, token: String) -> Result<(), RoomError> {
+        self.inner_connect(None, token).await
+    }
+
+    /// Performs all the stages of the [`RoomHandle::connect`], resetting
+    /// connection state and rejecting operations awaiting initialization if
+    /// any of them fails.
+    ///
+    /// Transport connection is skipped if no URL is provided.
+    async fn inner_connect(
+        &self,
+        url: Option<&str>,
+        token: String,
+    ) -> Result<(), RoomError> {
+        {
+            let mut room = self.0.borrow_mut();
+            if *room.state.connection.get() != ConnectionState::New {
+                return Err(RoomError::new(
+                    RoomErrorKind::Busy,
+                    "Room is already connecting or connected",
+                    MediaKinds::default(),
+                ));
+            }
+            room.state.connection.set(ConnectionState::Connecting);
+        }
+        let res = self.handshake(url, token).await;
+        let mut room = self.0.borrow_mut();
+        if res.is_err() && !room.is_closed {
+            room.state.connection.set(ConnectionState::New);
+            room.is_initialized.drop_when_subs();
+        }
+        res
+    }
+
+    /// Connects transport (if URL is provided), authorizes, joins the room
+    /// and waits for its initial state.
+    async fn handshake(
+        &self,
+        url: Option<&str>,
+        token: String,
+    ) -> Result<(), RoomError> {
+        let (timer, timeout_ms) = {
+            let room = self.0.borrow();
+            (Rc::clone(&room.timer), room.config.connect_timeout)
+        };
+        let error = |kind, message: &str| {
+            RoomError::new(kind, message, MediaKinds::default())
+        };
+
+        if let Some(url) = url {
+            let connected = self.0.borrow().ws.connect(url);
+            match future_with_timeout(&*timer, connected, timeout_ms).await {
+                Ok(Ok(())) => (),
+                Ok(Err(e)) => {
+                    return Err(error(RoomErrorKind::Disconnected, &e))
+                }
+                Err(timed_out) => {
+                    return Err(error(
+                        RoomErrorKind::Timeout,
+                        &format!("Connection {}", timed_out),
+                    ))
+                }
+            }
+        }
+
+        let hello = Command::Hello { token };
+        self.request(
+            hello,
+            "Hello handshake",
+            timeout_ms,
+            |event| match event {
+                Event::HelloAccepted => Some(Ok(())),
+                Event::HelloRejected { reason } => Some(Err(reason.clone())),
+                _ => None,
+            },
+        )
+        .await?
+        .map_err(|reason| error(RoomErrorKind::Unauthorized, &reason))?;
+
+        let role = self.0.borrow().config.role;
+        let join = Command::JoinRoom { role };
+        self.request(join, "Join", timeout_ms, |event| {
+            if let Event::RoomJoined { .. } = event {
+                Some(())
+            } else {
+                None
+            }
+        })
+        .await?;
+
+        let initialized = self.0.borrow_mut().is_initialized.when_eq(true);
+        match future_with_timeout(&*timer, initialized, timeout_ms).await {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(_)) => Err(error(RoomErrorKind::Closed, "Room was closed")),
+            Err(timed_out) => Err(error(
+                RoomErrorKind::Timeout,
+                &format!("Initial state sync {}", timed_out),
+            )),
+        }
+    }
+
+    /// Sends provided [`Command`] and waits for the first [`Event`], which
+    /// the provided function maps to `Some`, during the provided timeout.
+    async fn request<T, F>(
+        &self,
+        cmd: Command,
+        stage: &str,
+        timeout_ms: i32,
+        reply: F,
+    ) -> Result<T, RoomError>
+    where
+        F: Fn(&Event) -> Option<T> + 'static,
+    {
+        let (tx, rx) = mpsc::unbounded();
+        let timer = {
+            let mut room = self.0.borrow_mut();
+            room.event_subs.push(tx);
+            room.send(cmd);
+            Rc::clone(&room.timer)
+        };
+        let mut replies =
+            rx.filter_map(move |event| futures::future::ready(reply(&event)));
+        match future_with_timeout(&*timer, replies.next(), timeout_ms).await {
+            Ok(Some(reply)) => Ok(reply),
+            Ok(None) => Err(RoomError::new(
+                RoomErrorKind::Closed,
+                "Room was closed",
+                MediaKinds::default(),
+            )),
+            Err(timed_out) => Err(RoomError::new(
+                RoomErrorKind::Timeout,
+                format!("{} {}", stage, timed_out),
+                MediaKinds::default(),
+            )),
+        }
+    }
+
+    /// Performs [`RoomHandle::hold`], resolving with `None` if the room is
+    /// already on hold.
+    ///
+    /// [`Command::HoldRoom`] is sent only once mute succeeds, and the room is
+    /// put on hold once server confirms it with [`Event::RoomHeld`].
+    fn inner_hold(
+        &self,
+    ) -> LocalBoxFuture<'static, Result<Option<OperationReport>, RoomError>>
+    {
+        let room = self.0.borrow();
+        if room.hold_snapshot.is_some() {
+            return futures::future::ok(None).boxed_local();
+        }
+        let snapshot: HoldSnapshot = room
+            .peers
+            .iter()
+            .map(|(id, peer)| (*id, peer.mute_snapshot()))
+            .collect();
+        let timeout = room.config.mute_timeout;
+        drop(room);
+
+        let fut = self.inner_mute(MediaKinds::all(), timeout);
+        let this = self.clone();
+        async move {
+            let report = fut.await?;
+            this.signal_hold(true, timeout).await?;
+            this.0.borrow_mut().hold_snapshot.get_or_insert(snapshot);
+            Ok(Some(report))
+        }
+        .boxed_local()
+    }
+
+    /// Performs [`RoomHandle::resume`].
+    ///
+    /// [`Command::ResumeRoom`] is sent only once unmute succeeds, and the
+    /// room is taken off hold once server confirms it with
+    /// [`Event::RoomResumed`].
+    fn inner_resume(&self) -> LocalBoxFuture<'static, Result<(), RoomError>> {
+        let room = self.0.borrow();
+        let snapshot = match &room.hold_snapshot {
+            Some(snapshot) => snapshot.clone(),
+            None => return futures::future::ok(()).boxed_local(),
+        };
+        let timeout = room.config.unmute_timeout;
+        drop(room);
+
+        let mut send = MediaKinds::default();
+        let mut recv =
+            MediaKinds::default().with_direction(MediaDirection::Recv);
+        for (media, _, is_muted) in snapshot.values().flatten() {
+            if *is_muted {
+                continue;
+            }
+            if media.direction.is_send() {
+                send.merge(*media);
+            } else {
+                recv.merge(*media);
+            }
+        }
+        let unmutes: Vec<_> = [send, recv]
+            .iter()
+            .filter(|media| !media.is_empty())
+            .map(|media| self.inner_unmute(*media, timeout))
+            .collect();
+
+        let this = self.clone();
+        async move {
+            for res in futures::future::join_all(unmutes).await {
+                res?;
+            }
+            this.signal_hold(false, timeout).await?;
+            this.0.borrow_mut().hold_snapshot = None;
+            let mutes = this.restore_muted(&snapshot);
+            for res in futures::future::join_all(mutes).await {
+                res?;
+            }
+            Ok(())
+        }
+        .boxed_local()
+    }
+
+    /// Sends [`Command::HoldRoom`] (or [`Command::ResumeRoom`] if `is_held`
+    /// is `false`) and waits for its confirmation by server during the
+    /// provided timeout.
+    ///
+    /// The opposite command is sent if it's not confirmed, so server doesn't
+    /// keep it applied if it's delivered late.
+    async fn signal_hold(
+        &self,
+        is_held: bool,
+        timeout_ms: i32,
+    ) -> Result<(), RoomError> {
+        let (cmd, stage, confirmation, undo) = if is_held {
+            (
+                Command::HoldRoom,
+                "Hold",
+                Event::RoomHeld,
+                Command::ResumeRoom,
+            )
+        } else {
+            (
+                Command::ResumeRoom,
+                "Resume",
+                Event::RoomResumed,
+                Command::HoldRoom,
+            )
+        };
+        let res = self
+            .request(cmd, stage, timeout_ms, move |event| {
+                (*event == confirmation).then_some(())
+            })
+            .await;
+        let mut room = self.0.borrow_mut();
+        if let Err(err) = &res {
+            if !room.is_closed {
+                room.send(undo);
+            }
+            room.dispatch("error", || err.clone().into());
+        }
+        res
+    }
+
+    /// Mutes back via server the [`Sender`]s, which were muted before hold
+    /// according to the provided [`HoldSnapshot`], but are unmuted by
+    /// [`RoomHandle::resume`] along with the rest of the room.
+    ///
+    /// Incoming media can't be muted for a single peer, so it's left as
+    /// server unmuted it.
+    fn restore_muted(
+        &self,
+        snapshot: &HoldSnapshot,
+    ) -> Vec<LocalBoxFuture<'static, Result<(), RoomError>>> {
+        snapshot
+            .iter()
+            .flat_map(|(id, tracks)| {
+                tracks.iter().filter_map(move |(_, track_id, is_muted)| {
+                    let track_id = track_id.as_ref().filter(|_| *is_muted)?;
+                    Some(self.mute_track(*id, track_id.clone()))
+                })
+            })
+            .collect()
+    }
+
+    /// Mutes the [`Sender`] with the provided ID of the provided peer via
+    /// [`Command::MuteTrack`], resolving once server confirms it.
+    fn mute_track(
+        &self,
+        peer_id: PeerId,
+        track_id: String,
+    ) -> LocalBoxFuture<'static, Result<(), RoomError>> {
+        self.dispatching_error(self.start_mute_track(peer_id, track_id))
+    }
+
+    /// Starts mute of the [`Sender`] with the provided ID of the provided
+    /// peer, as described in the [`RoomHandle::mute_track`].
+    fn start_mute_track(
+        &self,
+        peer_id: PeerId,
+        track_id: String,
+    ) -> LocalBoxFuture<'static, Result<(), RoomError>> {
+        let room = Rc::clone(&self.0);
+        let mut room_ref = room.borrow_mut();
+        let op_id = room_ref.next_op_id();
+        let optimistic = room_ref.optimistic_mute;
+        let timeout_ms = room_ref.config.mute_timeout;
+        let sender = match room_ref.sender_mut(peer_id, &track_id) {
+            Some(sender) => sender,
+            None => {
+                let err = RoomError::new(
+                    RoomErrorKind::Dropped,
+                    "Track was removed",
+                    MediaKinds::default(),
+                );
+                return futures::future::err(err).boxed_local();
+            }
+        };
+        let media = MediaKinds::of(sender.kind, MediaDirection::Send);
+        let is_muting = *sender.state.get() == MuteState::Muting;
+        let confirmation = match sender.on_mute(op_id, optimistic) {
+            Ok(confirmation) => confirmation,
+            // Already muted.
+            Err(_) => return futures::future::ok(()).boxed_local(),
+        };
+        room_ref.update_state();
+        if !is_muting {
+            room_ref.send(Command::MuteTrack {
+                peer_id: peer_id.0,
+                track_id: track_id.clone(),
+            });
+        }
+        drop(room_ref);
+
+        async move {
+            let timer = Rc::clone(&room.borrow().timer);
+            let res =
+                future_with_timeout(&*timer, confirmation, timeout_ms).await;
+            let err = match res {
+                Ok(Ok(())) => return Ok(()),
+                Ok(Err(WhenError::Cancelled)) => RoomError::new(
+                    RoomErrorKind::Cancelled,
+                    "Mute was superseded by unmute",
+                    media,
+                ),
+                Ok(Err(WhenError::Dropped)) => RoomError::new(
+                    RoomErrorKind::Dropped,
+                    "Track was removed before mute confirmation",
+                    media,
+                ),
+                Err(timed_out) => {
+                    let mut room = room.borrow_mut();
+                    if let Some(sender) = room.sender_mut(peer_id, &track_id) {
+                        sender.rollback(op_id);
+                    }
+                    room.update_state();
+                    RoomError::new(
+                        RoomErrorKind::Timeout,
+                        format!("Mute confirmation {}", timed_out),
+                        media,
+                    )
+                    .with_peers(vec![peer_id.0])
+                }
+            };
+            Err(err)
+        }
+        .boxed_local()
+    }
+
+    /// Wraps the provided `Future`, so the `error` event is dispatched with
+    /// its [`RoomError`] once it fails.
+    fn dispatching_error<T, F>(
+        &self,
+        fut: F,
+    ) -> LocalBoxFuture<'static, Result<T, RoomError>>
+    where
+        T: 'static,
+        F: Future<Output = Result<T, RoomError>> + 'static,
+    {
+        let room = Rc::clone(&self.0);
+        async move {
+            let res = fut.await;
+            if let Err(err) = &res {
+                room.borrow().dispatch("error", || err.clone().into());
+            }
+            res
+        }
+        .boxed_local()
+    }
+
+    /// Performs the provided call by the task owning the [`Room`], returning
+    /// `Promise` of its result.
+    ///
+    /// Rejects with [`RoomErrorKind::Closed`] error of the provided
+    /// [`MediaKinds`] if the room is closed before the call is performed.
+    fn call_promise<F>(&self, media: MediaKinds, f: F) -> Promise
+    where
+        F: FnOnce(&RoomHandle) -> Promise + 'static,
+    {
+        let promise = self.call(f);
+        future_to_promise(async move {
+            match promise.await {
+                Some(promise) => JsFuture::from(promise).await,
+                None => Err(RoomError::new(
+                    RoomErrorKind::Closed,
+                    "Room was closed",
+                    media,
+                )
+                .into()),
+            }
+        })
+    }
+
+    /// Performs the provided call by the task owning the [`Room`], resolving
+    /// with its result, or `None` if the room is closed before the call is
+    /// performed.
+    fn call<T, F>(&self, f: F) -> impl Future<Output = Option<T>>
+    where
+        T: 'static,
+        F: FnOnce(&RoomHandle) -> T + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        self.post(move |this| {
+            let _ = tx.send(f(this));
+        });
+        rx.map(Result::ok)
+    }
+
+    /// Performs the provided call by the task owning the [`Room`], unless the
+    /// room is closed.
+    fn post<F>(&self, f: F)
+    where
+        F: FnOnce(&RoomHandle) + 'static,
+    {
+        let _ = self.1.unbounded_send(Box::new(f));
+    }
+
+    /// Mutes video when the page becomes hidden, and unmutes it back once
+    /// the page is visible again, unless it was muted before.
+    fn on_visibility_change(&self, is_hidden: bool) {
+        let media = MediaKinds::new(false, true);
+        let config = self.0.borrow().config;
+        if is_hidden {
+            let was_muted = self.0.borrow().will_be_muted(media);
+            self.0.borrow_mut().video_muted_before_hidden = Some(was_muted);
+            if !was_muted {
+                let fut = self.inner_mute(media, config.mute_timeout);
+                spawn_local(fut.map(drop));
+            }
+        } else {
+            let was_muted =
+                self.0.borrow_mut().video_muted_before_hidden.take();
+            if was_muted == Some(false) {
+                let fut = self.inner_unmute(media, config.unmute_timeout);
+                spawn_local(fut.map(drop));
+            }
+        }
+    }
+
+    /// Mutes provided kinds of media, joining already pending identical
+    /// operation (if any) instead of starting a new one.
+    fn inner_mute(
+        &self,
+        media: MediaKinds,
+        timeout_ms: i32,
+    ) -> SharedOperation {
+        let key = OperationKey {
+            kind: OperationKind::Mute,
+            media,
+            target: OperationTarget::Room,
+        };
+        let this = self.clone();
+        self.enqueue(key, move |id| {
+            this.start_mute(media, id, timeout_ms).boxed_local()
+        })
+    }
+
+    /// Unmutes provided kinds of media, joining already pending identical
+    /// operation (if any) instead of starting a new one.
+    fn inner_unmute(
+        &self,
+        media: MediaKinds,
+        timeout_ms: i32,
+    ) -> SharedOperation {
+        let is_force_muted = {
+            let room = self.0.borrow();
+            room.role == Role::Member
+                && room.peers.values().any(|peer| peer.is_force_muted(media))
+        };
+        if is_force_muted {
+            let err = RoomError::new(
+                RoomErrorKind::PermissionDenied,
+                "Media was muted by moderator",
+                media,
+            );
+            return self.dispatching_error(futures::future::err(err)).shared();
+        }
+
+        let key = OperationKey {
+            kind: OperationKind::Unmute,
+            media,
+            target: OperationTarget::Room,
+        };
+        let this = self.clone();
+        self.enqueue(key, move |id| {
+            this.start_unmute(media, id, timeout_ms).boxed_local()
+        })
+    }
+
+    /// Mutes audio and video of all peers except the provided ones as an
+    /// operation, which is never joined by other ones.
+    fn inner_mute_all_except(&self, peer_ids: Vec<i32>) -> SharedOperation {
+        let media = MediaKinds::new(true, true);
+        let (role, key, timeout_ms) = {
+            let mut room = self.0.borrow_mut();
+            let key = OperationKey {
+                kind: OperationKind::Mute,
+                media,
+                target: OperationTarget::Peers(room.next_op_id()),
+            };
+            (room.role, key, room.config.mute_timeout)
+        };
+        if role != Role::Moderator {
+            let err = RoomError::new(
+                RoomErrorKind::PermissionDenied,
+                "Only moderator can mute other peers",
+                media,
+            );
+            return self.dispatching_error(futures::future::err(err)).shared();
+        }
+
+        let this = self.clone();
+        self.enqueue(key, move |id| {
+            this.start_mute_peers(peer_ids, id, timeout_ms)
+                .boxed_local()
+        })
+    }
+
+    /// Starts operation with the provided [`OperationKey`] via the provided
+    /// function, returning `Promise` of its [`OperationReport`].
+    ///
+    /// Once the provided `AbortSignal` (if any) is aborted, `Promise` rejects
+    /// with [`RoomErrorKind::Cancelled`] error and is detached from the
+    /// operation, which is superseded by the opposite one only if no other
+    /// callers await it. Operation isn't started at all if the `AbortSignal`
+    /// is already aborted.
+    fn abortable<F>(
+        &self,
+        key: OperationKey,
+        signal: Option<AbortSignal>,
+        start: F,
+    ) -> Promise
+    where
+        F: FnOnce() -> SharedOperation,
+    {
+        let cancelled = move || -> JsValue {
+            RoomError::new(
+                RoomErrorKind::Cancelled,
+                "Operation was aborted",
+                key.media,
+            )
+            .into()
+        };
+        if signal.as_ref().is_some_and(AbortSignal::aborted) {
+            self.0.borrow().dispatch("error", cancelled);
+            return Promise::reject(&cancelled());
+        }
+        let fut = start();
+        let id = self.0.borrow().pending_ops.get(&key).map(|op| op.id);
+        let aborted = signal.map(Aborted::new);
+        let this = self.clone();
+        future_to_promise(async move {
+            let res = match aborted {
+                Some(aborted) => {
+                    match futures::future::select(fut, aborted).await {
+                        Either::Left((res, _)) => res,
+                        Either::Right(((), _)) => {
+                            if let Some(id) = id {
+                                this.detach(key, id);
+                            }
+                            this.0.borrow().dispatch("error", cancelled);
+                            return Err(cancelled());
+                        }
+                    }
+                }
+                None => fut.await,
+            };
+            let report = res?;
+            #[cfg(feature = "persist")]
+            Self::remember(key);
+            Ok(serde_wasm_bindgen::to_value(&report).unwrap())
+        })
+    }
+
+    /// Saves the user's choice to mute or unmute outgoing media of the
+    /// provided [`OperationKey`] as [`preferences::MutePreference`].
+    #[cfg(feature = "persist")]
+    fn remember(key: OperationKey) {
+        if !key.media.direction.overlaps(MediaDirection::Send) {
+            return;
+        }
+        let is_muted = key.kind == OperationKind::Mute;
+        let mut preference = preferences::load().unwrap_or_default();
+        if key.media.audio {
+            preference.audio = is_muted;
+        }
+        if key.media.video {
+            preference.video = is_muted;
+        }
+        preferences::save(preference);
+    }
+
+    /// Detaches a caller from the pending operation with the provided
+    /// [`OperationKey`] and ID (if any), starting the opposite operation, so
+    /// it's cancelled or rolled back, once no other callers await it.
+    fn detach(&self, key: OperationKey, id: u64) {
+        let (is_last, config) = {
+            let room = self.0.borrow();
+            let is_last = match room.pending_ops.get(&key) {
+                Some(op) if op.id == id => {
+                    op.subscribers.set(op.subscribers.get() - 1);
+                    op.subscribers.get() == 0
+                }
+                _ => false,
+            };
+            (is_last, room.config)
+        };
+        if !is_last {
+            return;
+        }
+        let fut = match key.kind {
+            OperationKind::Mute => {
+                self.inner_unmute(key.media, config.unmute_timeout)
+            }
+            OperationKind::Unmute => {
+                self.inner_mute(key.media, config.mute_timeout)
+            }
+        };
+        spawn_local(fut.map(drop));
+    }
+
+    /// Dispatches event with the provided name to its listeners on each
+    /// change yielded by the provided `Stream`, passing the value returned by
+    /// the provided function.
+    fn dispatch_changes<S, F>(&self, mut changes: S, name: &'static str, f: F)
+    where
+        S: Stream<Item = ()> + Unpin + 'static,
+        F: Fn(&Room) -> JsValue + 'static,
+    {
+        let room = Rc::downgrade(&self.0);
+        spawn_local(async move {
+            while changes.next().await.is_some() {
+                let room = match room.upgrade() {
+                    Some(room) => room,
+                    None => break,
+                };
+                let room = room.borrow();
+                room.dispatch(name, || f(&room));
+            }
+        });
+    }
+
+    /// Returns pending operation with the provided [`OperationKey`] or
+    /// enqueues a new one, which will be started with the provided function
+    /// called with the ID of the operation.
+    ///
+    /// New operation is started only after the room is initialized and all
+    /// the pending operations affecting the same kinds of media are
+    /// completed, so operations are
+    /// confirmed strictly in order. Pending operations superseded by the new
+    /// one are collapsed: they are not waited for, and are cancelled if not
+    /// started yet.
+    fn enqueue<F>(&self, key: OperationKey, start: F) -> SharedOperation
+    where
+        F: FnOnce(u64) -> LocalBoxFuture<'static, OperationResult> + 'static,
+    {
+        if self.0.borrow().is_closed {
+            let err = RoomError::new(
+                RoomErrorKind::Closed,
+                "Room was closed",
+                key.media,
+            );
+            return self.dispatching_error(futures::future::err(err)).shared();
+        }
+        if let Some(op) = self.0.borrow().pending_ops.get(&key) {
+            op.subscribers.set(op.subscribers.get() + 1);
+            return op.fut.clone();
+        }
+
+        let mut room = self.0.borrow_mut();
+        let mut preceding = Vec::new();
+        room.pending_ops.retain(|pending_key, pending| {
+            if key.supersedes(pending_key) {
+                pending.is_collapsed.set(true);
+                false
+            } else {
+                if key.overlaps(pending_key) {
+                    preceding.push(pending.fut.clone());
+                }
+                true
+            }
+        });
+        let id = room.next_op_id();
+        let is_collapsed = Rc::new(Cell::new(false));
+        let initialized = room.is_initialized.when_eq(true);
+
+        let room_rc = Rc::clone(&self.0);
+        let is_op_collapsed = Rc::clone(&is_collapsed);
+        let fut = async move {
+            // Room being closed before initialization is reported by the
+            // operation itself.
+            let is_initialized = initialized.await.is_ok();
+            futures::future::join_all(preceding).await;
+            let is_failed = !is_initialized && !room_rc.borrow().is_closed;
+            let res = if is_failed {
+                Err(RoomError::new(
+                    RoomErrorKind::Disconnected,
+                    "Connection failed before operation start",
+                    key.media,
+                ))
+            } else if is_op_collapsed.get() {
+                Err(RoomError::new(
+                    RoomErrorKind::Cancelled,
+                    "Operation was superseded before start",
+                    key.media,
+                ))
+            } else {
+                start(id).await
+            };
+
+            let mut room = room_rc.borrow_mut();
+            let is_same_op =
+                room.pending_ops.get(&key).map(|op| op.id) == Some(id);
+            if is_same_op {
+                room.pending_ops.remove(&key);
+            }
+            if let Err(err) = &res {
+                room.dispatch("error", || err.clone().into());
+            }
+            res
+        }
+        .boxed_local()
+        .shared();
+        room.pending_ops.insert(
+            key,
+            PendingOperation {
+                id,
+                fut: fut.clone(),
+                is_collapsed,
+                subscribers: Cell::new(1),
+            },
+        );
+        fut
+    }
+
+    /// Starts mute of audio and video of all peers except the provided ones
+    /// as the operation with the provided ID.
+    ///
+    /// [`Command::MutePeer`] is sent to each peer having [`Track`]s to mute
+    /// and is resent according to the retry policy. Mute of the peers, which
+    /// isn't confirmed, is rolled back, so no [`Track`] is left muting.
+    fn start_mute_peers(
+        &self,
+        except: Vec<i32>,
+        op_id: u64,
+        timeout_ms: i32,
+    ) -> impl Future<Output = OperationResult> {
+        let media = MediaKinds::new(true, true);
+        let confirmations: Vec<_> = {
+            let mut room = self.0.borrow_mut();
+            let optimistic = room.optimistic_mute;
+            let confirmations: Vec<_> = room
+                .peers
+                .iter_mut()
+                .filter(|(id, _)| !except.contains(&id.0))
+                .map(|(id, peer)| {
+                    let on_mute = peer.on_mute(media, op_id, optimistic);
+                    let cmd = Command::MutePeer {
+                        peer_id: id.0,
+                        audio: media.audio,
+                        video: media.video,
+                    };
+                    let cmd = (!on_mute.is_empty()).then_some(cmd);
+                    (*id, on_mute, cmd)
+                })
+                .collect();
+            for (_, _, cmd) in &confirmations {
+                if let Some(cmd) = cmd {
+                    room.send(cmd.clone());
+                }
+            }
+            room.update_state();
+            confirmations
+        };
+
+        let room = Rc::clone(&self.0);
+        async move {
+            let results = futures::future::join_all(
+                confirmations.into_iter().map(|(id, on_mute, cmd)| {
+                    let room = Rc::clone(&room);
+                    async move {
+                        let on_mute = futures::future::join_all(on_mute);
+                        let res = confirm_with_retries(
+                            &room, on_mute, cmd, timeout_ms,
+                        )
+                        .await;
+                        let is_muted = matches!(
+                            res,
+                            Ok(res) if res.iter().all(Result::is_ok),
+                        );
+                        (id, is_muted)
+                    }
+                }),
+            )
+            .await;
+
+            let mut room = room.borrow_mut();
+            if room.is_closed {
+                return Err(RoomError::new(
+                    RoomErrorKind::Closed,
+                    "Room was closed",
+                    media,
+                ));
+            }
+            for (id, _) in results.iter().filter(|(_, is_muted)| !is_muted) {
+                if let Some(peer) = room.peers.get_mut(id) {
+                    peer.rollback(media, op_id);
+                }
+            }
+            room.update_state();
+            let mut report = room.report(media, MuteState::Muted);
+            let is_target = |track: &TrackOutcome| {
+                results.iter().any(|(id, _)| id.0 == track.peer_id)
+            };
+            report.confirmed.retain(is_target);
+            report.failed.retain(is_target);
+            Ok(report)
+        }
+    }
+
+    /// Starts mute of the provided kinds as the operation with the provided
+    /// ID.
+    ///
+    /// [`Command::MuteRoom`] is sent whenever any [`Track`] starts muting,
+    /// since the queue guarantees that no other operation on the same media
+    /// awaits confirmation at this point.
+    fn start_mute(
+        &self,
+        media: MediaKinds,
+        op_id: u64,
+        timeout_ms: i32,
+    ) -> impl Future<Output = OperationResult> {
+        let mut room = self.0.borrow_mut();
+        let optimistic = room.optimistic_mute;
+        let on_mute_fut: Vec<_> = room
+            .peers
+            .values_mut()
+            .flat_map(|peer| peer.on_mute(media, op_id, optimistic))
+            .collect();
+        room.update_state();
+
+        let cmd = if !on_mute_fut.is_empty() {
+            let cmd = Command::MuteRoom {
+                audio: media.audio,
+                video: media.video,
+                display: media.display,
+                direction: media.direction,
+            };
+            room.stats.command_sent(OperationKey {
+                kind: OperationKind::Mute,
+                media,
+                target: OperationTarget::Room,
+            });
+            room.send(cmd.clone());
+            Some(cmd)
+        } else {
+            None
+        };
+        let room = Rc::clone(&self.0);
+        async move {
+            let res = confirm_with_retries(
+                &room,
+                futures::future::join_all(on_mute_fut),
+                cmd,
+                timeout_ms,
+            )
+            .await;
+            if room.borrow().is_closed {
+                return Err(RoomError::new(
+                    RoomErrorKind::Closed,
+                    "Room was closed",
+                    media,
+                ));
+            }
+            match res {
+                Ok(res) if res.contains(&Err(WhenError::Cancelled)) => {
+                    Err(RoomError::new(
+                        RoomErrorKind::Cancelled,
+                        "Mute was superseded by unmute",
+                        media,
+                    ))
+                }
+                Ok(res) if res.contains(&Err(WhenError::Dropped)) => {
+                    Err(RoomError::new(
+                        RoomErrorKind::Dropped,
+                        "Track was removed before mute confirmation",
+                        media,
+                    ))
+                }
+                Ok(_) => Ok(room.borrow().report(media, MuteState::Muted)),
+                Err(timed_out) => {
+                    let report = {
+                        let mut room = room.borrow_mut();
+                        let report = room.report(media, MuteState::Muted);
+                        room.time_out(
+                            media,
+                            op_id,
+                            OperationKind::Mute,
+                            &report,
+                        );
+                        report
+                    };
+                    if report.confirmed.is_empty() {
+                        return Err(RoomError::new(
+                            RoomErrorKind::Timeout,
+                            format!("Mute confirmation {}", timed_out),
+                            media,
+                        )
+                        .with_peers(report.failed_peers()));
+                    } else if report.failed.is_empty() {
+                        return Ok(report);
+                    }
+
+                    let (rolled_back, on_unmute, cmd, timeout_ms) = {
+                        let mut room = room.borrow_mut();
+                        let (rolled_back, on_unmute, cmd) =
+                            room.undo_mute(media, op_id, &report);
+                        let timeout_ms = room.config.unmute_timeout;
+                        (rolled_back, on_unmute, cmd, timeout_ms)
+                    };
+                    let on_unmute = futures::future::join_all(on_unmute);
+                    let undone = confirm_with_retries(
+                        &room,
+                        on_unmute,
+                        Some(cmd),
+                        timeout_ms,
+                    )
+                    .await;
+                    let mut room = room.borrow_mut();
+                    if room.is_closed {
+                        return Err(RoomError::new(
+                            RoomErrorKind::Closed,
+                            "Room was closed",
+                            media,
+                        ));
+                    }
+                    let err = match undone {
+                        Ok(_) => RoomError::new(
+                            RoomErrorKind::RolledBack,
+                            format!(
+                                "Mute confirmation of {} timed out, so mute \
+                                 of {} was rolled back",
+                                report.failed_kinds(),
+                                rolled_back,
+                            ),
+                            media,
+                        ),
+                        Err(undo_timed_out) => {
+                            room.rollback(rolled_back, op_id);
+                            RoomError::new(
+                                RoomErrorKind::Timeout,
+                                format!(
+                                    "Mute confirmation of {} {}, and \
+                                     rollback of mute of {} {}",
+                                    report.failed_kinds(),
+                                    timed_out,
+                                    rolled_back,
+                                    undo_timed_out,
+                                ),
+                                media,
+                            )
+                        }
+                    };
+                    Err(err.with_peers(report.failed_peers()))
+                }
+            }
+        }
+    }
+
+    /// Starts unmute of the provided kinds as the operation with the
+    /// provided ID.
+    ///
+    /// [`Command::UnmuteRoom`] is sent whenever any [`Track`] starts
+    /// unmuting, since the queue guarantees that no other operation on the
+    /// same media awaits confirmation at this point.
+    fn start_unmute(
+        &self,
+        media: MediaKinds,
+        op_id: u64,
+        timeout_ms: i32,
+    ) -> impl Future<Output = OperationResult> {
+        let mut room = self.0.borrow_mut();
+        let on_unmute_fut: Vec<_> = room
+            .peers
+            .values_mut()
+            .flat_map(|peer| peer.on_unmute(media, op_id))
+            .collect();
+        room.update_state();
+
+        let cmd = if !on_unmute_fut.is_empty() {
+            let cmd = Command::UnmuteRoom {
+                audio: media.audio,
+                video: media.video,
+                display: media.display,
+                direction: media.direction,
+            };
+            room.stats.command_sent(OperationKey {
+                kind: OperationKind::Unmute,
+                media,
+                target: OperationTarget::Room,
+            });
+            room.send(cmd.clone());
+            Some(cmd)
+        } else {
+            None
+        };
+        let room = Rc::clone(&self.0);
+        async move {
+            let res = confirm_with_retries(
+                &room,
+                futures::future::join_all(on_unmute_fut),
+                cmd,
+                timeout_ms,
+            )
+            .await;
+            if room.borrow().is_closed {
+                return Err(RoomError::new(
+                    RoomErrorKind::Closed,
+                    "Room was closed",
+                    media,
+                ));
+            }
+            match res {
+                Ok(res) if res.contains(&Err(WhenError::Cancelled)) => {
+                    Err(RoomError::new(
+                        RoomErrorKind::Cancelled,
+                        "Unmute was superseded by mute",
+                        media,
+                    ))
+                }
+                Ok(res) if res.contains(&Err(WhenError::Dropped)) => {
+                    Err(RoomError::new(
+                        RoomErrorKind::Dropped,
+                        "Track was removed before unmute confirmation",
+                        media,
+                    ))
+                }
+                Ok(_) => Ok(room.borrow().report(media, MuteState::Unmuted)),
+                Err(timed_out) => {
+                    let mut room = room.borrow_mut();
+                    let report = room.report(media, MuteState::Unmuted);
+                    room.time_out(media, op_id, OperationKind::Unmute, &report);
+                    if report.confirmed.is_empty() {
+                        Err(RoomError::new(
+                            RoomErrorKind::Timeout,
+                            format!("Unmute confirmation {}", timed_out),
+                            media,
+                        )
+                        .with_peers(report.failed_peers()))
+                    } else {
+                        Ok(report)
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Default for RoomHandle {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+/// Handle of a single outgoing track of the room.
+///
+/// Doesn't prevent the room from being dropped.
+#[wasm_bindgen]
+pub struct TrackHandle {
+    room: Weak<RefCell<Room>>,
+    peer_id: PeerId,
+    track_id: String,
+}
+
+#[wasm_bindgen]
+impl TrackHandle {
+    /// Mutes this track only.
+    ///
+    /// Resolves once mute is confirmed by server. Rejects with a
+    /// [`RoomError`] if it's not confirmed in time, or if the track is
+    /// removed or unmuted before.
+    #[wasm_bindgen(unchecked_return_type = "Promise<void>")]
+    pub fn mute(&self) -> Promise {
+        let room = match self.room() {
+            Some(room) => room,
+            None => {
+                let err = RoomError::new(
+                    RoomErrorKind::Closed,
+                    "Room was closed",
+                    MediaKinds::default(),
+                );
+                return Promise::reject(&err.into());
+            }
+        };
+        let (peer_id, track_id) = (self.peer_id, self.track_id.clone());
+        room.call_promise(MediaKinds::default(), move |room| {
+            let fut = room.mute_track(peer_id, track_id);
+            future_to_promise(async move {
+                fut.await?;
+                Ok(JsValue::UNDEFINED)
+            })
+        })
+    }
+
+    /// Subscribes provided callback to the changes of this track.
+    ///
+    /// Callback is called with the
+    /// `{ track_id, kind, muted, busy, peer_id }` object every time mute
+    /// state of this track changes, until the track is removed.
+    pub fn on_change(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "(track: TrackInfo) => void")]
+        callback: js_sys::Function,
+    ) {
+        let room = match self.room() {
+            Some(room) => room,
+            None => return,
+        };
+        let (peer_id, track_id) = (self.peer_id, self.track_id.clone());
+        room.post(move |room| {
+            let mut room_ref = room.0.borrow_mut();
+            let sender = match room_ref.sender_mut(peer_id, &track_id) {
+                Some(sender) => sender,
+                None => return,
+            };
+            let mut changes = sender.state.on_change();
+            let room = Rc::downgrade(&room.0);
+            spawn_local(async move {
+                while changes.next().await.is_some() {
+                    let room = match room.upgrade() {
+                        Some(room) => room,
+                        None => break,
+                    };
+                    let info = match room
+                        .borrow_mut()
+                        .sender_mut(peer_id, &track_id)
+                    {
+                        Some(sender) => sender.info(peer_id),
+                        None => break,
+                    };
+                    let info = serde_wasm_bindgen::to_value(&info).unwrap();
+                    let _ = callback.call1(&JsValue::NULL, &info);
+                }
+            });
+        });
+    }
+}
+
+impl TrackHandle {
+    /// Returns [`RoomHandle`] of the room of this track, unless the room is
+    /// dropped.
+    fn room(&self) -> Option<RoomHandle> {
+        let room = self.room.upgrade()?;
+        let calls = room.borrow().calls.clone();
+        Some(RoomHandle(room, calls))
+    }
+}
+
+#[derive(Debug)]
+struct PeerConnection {
+    tracks: Vec<Sender>,
+    receivers: Vec<Receiver>,
+
+    /// Underlying `RTCPeerConnection`, or `None` if WebRTC isn't supported.
+    #[cfg(feature = "media")]
+    rtc: Option<platform::RtcPeerConnection>,
+
+    /// [`MuteStrategy`] of the created [`Sender`]s.
+    #[cfg(feature = "media")]
+    mute_strategy: MuteStrategy,
+}
+
+impl PeerConnection {
+    #[cfg_attr(not(feature = "media"), allow(unused_variables))]
+    pub fn new(mute_strategy: MuteStrategy) -> Self {
+        let mut peer = Self {
+            tracks: Vec::new(),
+            receivers: vec![
+                Receiver::new(SenderKind::Video),
+                Receiver::new(SenderKind::Audio),
+            ],
+            #[cfg(feature = "media")]
+            rtc: platform::RtcPeerConnection::new().ok(),
+            #[cfg(feature = "media")]
+            mute_strategy,
+        };
+        peer.tracks = vec![
+            peer.new_sender("video", SenderKind::Video),
+            peer.new_sender("audio", SenderKind::Audio),
+        ];
+        peer
+    }
+
+    /// Creates [`Sender`] with its own transceiver in the underlying
+    /// `RTCPeerConnection` (if any).
+    fn new_sender<S: Into<String>>(&self, id: S, kind: SenderKind) -> Sender {
+        #[cfg_attr(not(feature = "media"), allow(unused_mut))]
+        let mut sender = Sender::new(id, kind);
+        #[cfg(feature = "media")]
+        {
+            sender.transceiver =
+                self.rtc.as_ref().map(|rtc| rtc.add_transceiver(kind));
+            sender.mute_strategy = self.mute_strategy;
+        }
+        sender
+    }
+
+    /// Adds screen sharing [`Sender`], if there is no one already.
+    pub fn add_display_sender(&mut self) {
+        let has_display =
+            self.tracks.iter().any(|s| s.kind == SenderKind::Display);
+        if !has_display {
+            let sender = self.new_sender("display", SenderKind::Display);
+            self.tracks.push(sender);
+        }
+    }
+
+    /// Removes screen sharing [`Sender`] (if any).
+    pub fn remove_display_sender(&mut self) {
+        self.tracks.retain(|s| s.kind != SenderKind::Display);
+    }
+
+    /// Adds [`Sender`] of the provided kind for the track with the provided
+    /// ID, if there is no one already.
+    pub fn add_sender(&mut self, kind: SenderKind, track_id: String) {
+        if !self.tracks.iter().any(|s| s.id == track_id) {
+            let sender = self.new_sender(track_id, kind);
+            self.tracks.push(sender);
+        }
+    }
+
+    /// Removes [`Sender`] of the track with the provided ID (if any).
+    ///
+    /// Its pending transitions resolve with [`WhenError::Dropped`].
+    pub fn remove_sender(&mut self, track_id: &str) {
+        self.tracks.retain(|s| s.id != track_id);
+    }
+
+    /// Reconciles [`Sender`]s with the provided server's snapshot, setting
+    /// their mute state silently.
+    pub fn apply_snapshot(&mut self, senders: &[proto::SenderState]) {
+        self.tracks
+            .retain(|s| senders.iter().any(|state| state.track_id == s.id));
+        for state in senders {
+            self.add_sender(state.kind, state.track_id.clone());
+            let sender = self
+                .tracks
+                .iter_mut()
+                .find(|s| s.id == state.track_id)
+                .unwrap();
+            let target = if state.muted {
+                MuteState::Muted
+            } else {
+                MuteState::Unmuted
+            };
+            if sender.state.get().target() != target {
+                sender.state.drop_when_subs();
+            }
+            if state.muted {
+                sender.mute();
+            } else {
+                sender.unmute();
+            }
+        }
+    }
+
+    /// Returns [`Sender`]s and/or [`Receiver`]s of the provided kinds and
+    /// direction.
+    pub fn filter_tracks_by_kind_mut(
+        &mut self,
+        media: MediaKinds,
+    ) -> impl Iterator<Item = &mut dyn Track> {
+        let (send, recv) =
+            (media.direction.is_send(), media.direction.is_recv());
+        let senders = self
+            .tracks
+            .iter_mut()
+            .filter(move |_| send)
+            .map(|sender| sender as &mut dyn Track);
+        let receivers = self
+            .receivers
+            .iter_mut()
+            .filter(move |_| recv)
+            .map(|receiver| receiver as &mut dyn Track);
+        senders
+            .chain(receivers)
+            .filter(move |track| media.contains(track.kind()))
+    }
+
+    /// Returns [`Sender`]s and/or [`Receiver`]s of the provided kinds and
+    /// direction.
+    pub fn filter_tracks_by_kind(
+        &self,
+        media: MediaKinds,
+    ) -> impl Iterator<Item = &dyn Track> {
+        let (send, recv) =
+            (media.direction.is_send(), media.direction.is_recv());
+        let senders = self
+            .tracks
+            .iter()
+            .filter(move |_| send)
+            .map(|sender| sender as &dyn Track);
+        let receivers = self
+            .receivers
+            .iter()
+            .filter(move |_| recv)
+            .map(|receiver| receiver as &dyn Track);
+        senders
+            .chain(receivers)
+            .filter(move |track| media.contains(track.kind()))
+    }
+
+    /// Applies confirmed mute, returning `true` if any [`Track`] was
+    /// muted.
+    pub fn mute(&mut self, media: MediaKinds) -> bool {
+        let mut is_changed = false;
+        self.filter_tracks_by_kind_mut(media)
+            .filter(|track| !track.is_muted())
+            .for_each(|track| {
+                track.mute();
+                is_changed = true;
+            });
+        is_changed
+    }
+
+    /// Applies confirmed unmute, returning `true` if any [`Track`] was
+    /// unmuted.
+    pub fn unmute(&mut self, media: MediaKinds) -> bool {
+        let mut is_changed = false;
+        self.filter_tracks_by_kind_mut(media)
+            .filter(|track| track.is_muted())
+            .for_each(|track| {
+                track.unmute();
+                is_changed = true;
+            });
+        is_changed
+    }
+
+    /// Starts mute of the provided kinds by the operation with the provided
+    /// ID, returning `Future`s which resolve once mute of the each [`Track`]
+    /// is confirmed.
+    ///
+    /// If `optimistic` is `true` then tracks are disabled right away.
+    pub fn on_mute(
+        &mut self,
+        media: MediaKinds,
+        op_id: u64,
+        optimistic: bool,
+    ) -> Vec<When> {
+        self.filter_tracks_by_kind_mut(media)
+            .filter_map(|track| track.on_mute(op_id, optimistic).ok())
+            .collect()
+    }
+
+    /// Starts unmute of the provided kinds by the operation with the
+    /// provided ID, returning `Future`s which resolve once unmute of the each
+    /// [`Track`] is confirmed.
+    pub fn on_unmute(&mut self, media: MediaKinds, op_id: u64) -> Vec<When> {
+        self.filter_tracks_by_kind_mut(media)
+            .filter_map(|track| track.on_unmute(op_id).ok())
+            .collect()
+    }
+
+    pub fn mute_local(&mut self, media: MediaKinds) {
+        self.filter_tracks_by_kind_mut(media)
+            .for_each(|track| track.mute_local());
+    }
+
+    pub fn unmute_local(&mut self, media: MediaKinds) {
+        self.filter_tracks_by_kind_mut(media)
+            .for_each(|track| track.unmute_local());
+    }
+
+    pub fn rollback(&mut self, media: MediaKinds, op_id: u64) {
+        self.filter_tracks_by_kind_mut(media)
+            .for_each(|track| track.rollback(op_id));
+    }
+
+    /// Mutes [`Sender`]s of the provided kinds by moderator's request without
+    /// waiting for server's confirmation, so they can't be unmuted until
+    /// [`PeerConnection::release_force_mute`] is called.
+    pub fn force_mute(&mut self, media: MediaKinds) {
+        self.tracks
+            .iter_mut()
+            .filter(|sender| media.contains(sender.kind))
+            .for_each(|sender| {
+                sender.is_force_muted = true;
+                sender.mute_local();
+            });
+    }
+
+    /// Allows unmuting [`Sender`]s of the provided kinds muted by moderator.
+    pub fn release_force_mute(&mut self, media: MediaKinds) {
+        self.tracks
+            .iter_mut()
+            .filter(|sender| media.contains(sender.kind))
+            .for_each(|sender| sender.is_force_muted = false);
+    }
+
+    /// Indicates whether any [`Sender`] of the provided kinds is muted by
+    /// moderator.
+    pub fn is_force_muted(&self, media: MediaKinds) -> bool {
+        media.direction.is_send()
+            && self.tracks.iter().any(|sender| {
+                media.contains(sender.kind) && sender.is_force_muted
+            })
+    }
+
+    /// Returns mute flags of all [`Track`]s along with their kinds.
+    pub fn mute_snapshot(&self) -> Vec<(MediaKinds, Option<String>, bool)> {
+        let senders = self.tracks.iter().map(|sender| {
+            let media = MediaKinds::of(sender.kind, MediaDirection::Send);
+            (media, Some(sender.id.clone()), sender.is_muted())
+        });
+        let receivers = self.receivers.iter().map(|receiver| {
+            let media = MediaKinds::of(receiver.kind, MediaDirection::Recv);
+            (media, None, receiver.is_muted())
+        });
+        senders.chain(receivers).collect()
+    }
+}
+
+impl SenderKind {
+    /// Returns [`SenderKind`] of the provided `MediaStreamTrack`, or `None`
+    /// if its kind is unknown.
+    #[cfg(feature = "media")]
+    pub fn of_track(track: &media::MediaStreamTrack) -> Option<Self> {
+        match track.kind().as_str() {
+            "audio" => Some(Self::Audio),
+            "video" => Some(Self::Video),
+            _ => None,
+        }
+    }
+}
+
+/// Mute state of a [`Sender`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MuteState {
+    /// [`Sender`] is unmuted.
+    Unmuted,
+
+    /// Mute was requested, but not confirmed by server yet.
+    Muting,
+
+    /// [`Sender`] is muted.
+    Muted,
+
+    /// Unmute was requested, but not confirmed by server yet.
+    Unmuting,
+}
+
+impl MuteState {
+    /// Indicates whether transition from this state to the provided one is
+    /// allowed.
+    pub fn can_transit_to(self, to: MuteState) -> bool {
+        use MuteState::*;
+
+        match (self, to) {
+            (from, to) if from == to => true,
+            (Unmuted, Muting)
+            | (Unmuted, Muted)
+            | (Muting, Muted)
+            | (Muting, Unmuted)
+            | (Muting, Unmuting)
+            | (Muted, Unmuting)
+            | (Muted, Unmuted)
+            | (Unmuting, Unmuted)
+            | (Unmuting, Muted)
+            | (Unmuting, Muting) => true,
+            _ => false,
+        }
+    }
+
+    /// Indicates whether media is muted in this state (i.e. mute is confirmed
+    /// and unmute is not confirmed yet).
+    pub fn is_muted(self) -> bool {
+        matches!(self, MuteState::Muted | MuteState::Unmuting)
+    }
+
+    /// Returns [`MuteState`] which this one will end up with once its
+    /// transition is confirmed.
+    pub fn target(self) -> Self {
+        match self {
+            MuteState::Muting | MuteState::Muted => MuteState::Muted,
+            MuteState::Unmuting | MuteState::Unmuted => MuteState::Unmuted,
+        }
+    }
+}
+
+/// Mute state of a single media kind aggregated across all peers.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum AggregateMuteState {
+    /// All [`Sender`]s are unmuted.
+    #[default]
+    Unmuted,
+
+    /// All [`Sender`]s are muted.
+    Muted,
+
+    /// Some [`Sender`]s are muted and others are not.
+    PartiallyMuted,
+
+    /// Some [`Sender`]s are awaiting confirmation of mute or unmute.
+    InProgress,
+}
+
+impl AggregateMuteState {
+    /// Aggregates provided [`MuteState`]s.
+    pub fn from_states(states: impl Iterator<Item = MuteState>) -> Self {
+        let (mut muted, mut unmuted) = (false, false);
+        for state in states {
+            match state {
+                MuteState::Muting | MuteState::Unmuting => {
+                    return Self::InProgress;
+                }
+                MuteState::Muted => muted = true,
+                MuteState::Unmuted => unmuted = true,
+            }
+        }
+        match (muted, unmuted) {
+            (true, true) => Self::PartiallyMuted,
+            (true, false) => Self::Muted,
+            (false, _) => Self::Unmuted,
+        }
+    }
+}
+
+/// Mute state of the [`Room`] returned to JS.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+struct RoomMuteState {
+    audio: AggregateMuteState,
+    video: AggregateMuteState,
+    display: AggregateMuteState,
+}
+
+/// Outcome of the operation for a single [`Track`].
+#[derive(Clone, Debug, Serialize)]
+struct TrackOutcome {
+    peer_id: i32,
+    kind: SenderKind,
+    direction: MediaDirection,
+}
+
+/// Result of the mute or unmute operation returned to JS.
+#[derive(Clone, Debug, Default, Serialize)]
+struct OperationReport {
+    /// [`Track`]s which transition was confirmed by server.
+    confirmed: Vec<TrackOutcome>,
+
+    /// [`Track`]s which transition wasn't confirmed in time and was rolled
+    /// back.
+    failed: Vec<TrackOutcome>,
+}
+
+/// Notification of the [`RoomHandle::replace_track`] passed to JS.
+#[cfg(feature = "media")]
+#[derive(Debug, Serialize)]
+struct TrackReplaced {
+    kind: SenderKind,
+    muted: bool,
+}
+
+impl OperationReport {
+    /// Returns [`MediaKinds`] of the failed [`Track`]s.
+    pub fn failed_kinds(&self) -> MediaKinds {
+        let mut kinds = MediaKinds::default();
+        for track in &self.failed {
+            kinds.merge(MediaKinds::of(track.kind, track.direction));
+        }
+        kinds
+    }
+
+    /// Returns IDs of the peers having failed [`Track`]s.
+    pub fn failed_peers(&self) -> Vec<i32> {
+        let mut peers: Vec<_> =
+            self.failed.iter().map(|track| track.peer_id).collect();
+        peers.sort_unstable();
+        peers.dedup();
+        peers
+    }
+}
+
+/// Result of the [`RoomHandle::mute_all_except`] returned to JS.
+#[derive(Debug, Default, Serialize)]
+struct PeersMuteReport {
+    /// IDs of the peers which mute was confirmed.
+    muted: Vec<i32>,
+
+    /// IDs of the peers which mute wasn't confirmed.
+    failed: Vec<i32>,
+}
+
+impl From<OperationReport> for PeersMuteReport {
+    fn from(report: OperationReport) -> Self {
+        let failed = report.failed_peers();
+        let mut muted: Vec<_> = report
+            .confirmed
+            .iter()
+            .map(|track| track.peer_id)
+            .filter(|id| !failed.contains(id))
+            .collect();
+        muted.sort_unstable();
+        muted.dedup();
+        Self { muted, failed }
+    }
+}
+
+/// Description of an outgoing track passed to JS.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+struct TrackInfo {
+    track_id: String,
+    kind: SenderKind,
+
+    /// Indicates whether mute of the track is confirmed.
+    muted: bool,
+
+    /// Indicates whether mute or unmute of the track is in progress.
+    busy: bool,
+    peer_id: i32,
+}
+
+/// Description of a peer and its outgoing tracks passed to JS.
+#[derive(Clone, Debug, Serialize)]
+struct PeerInfo {
+    peer_id: i32,
+
+    /// Outgoing tracks sorted by ID.
+    tracks: Vec<TrackInfo>,
+}
+
+/// State of a [`Room`] passed to JS.
+#[derive(Clone, Debug, Serialize)]
+struct RoomInfo {
+    connection: ConnectionState,
+    role: Role,
+    mute: RoomMuteState,
+
+    /// Peers sorted by ID.
+    peers: Vec<PeerInfo>,
+}
+
+/// Media muted by moderator passed to JS.
+#[derive(Clone, Copy, Debug, Serialize)]
+struct ForceMuted {
+    audio: bool,
+    video: bool,
+}
+
+/// Mute state of a remote participant passed to JS.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+struct RemoteMuteState {
+    peer_id: i32,
+    audio: bool,
+    video: bool,
+}
+
+/// State of a [`Room`] captured for comparison in tests, with all the
+/// collections sorted, so its `Debug` representation is deterministic.
+#[cfg(feature = "test-utils")]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RoomSnapshot {
+    connection: ConnectionState,
+    role: Role,
+
+    /// Outgoing tracks sorted by peer and track IDs.
+    tracks: Vec<TrackInfo>,
+
+    /// Operations in progress in the order they were started.
+    pending_ops: Vec<(OperationKind, MediaKinds)>,
+
+    /// Known mute states of the remote participants sorted by peer ID.
+    remote_mutes: Vec<RemoteMuteState>,
+}
+
+/// State of the connection with server.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum ConnectionState {
+    /// [`RoomHandle::connect`] wasn't called yet or failed.
+    New,
+
+    /// [`RoomHandle::connect`] is in progress.
+    Connecting,
+
+    /// Room is joined.
+    Connected,
+
+    /// Room is closed.
+    Closed,
+}
+
+/// Aggregated state of the [`Room`], which changes can be observed.
+#[derive(Debug)]
+struct RoomState {
+    connection: ReactiveField<ConnectionState>,
+    mute: ReactiveField<RoomMuteState>,
+    peer_count: ReactiveField<usize>,
+}
+
+impl RoomState {
+    pub fn new() -> Self {
+        Self {
+            connection: ReactiveField::new(ConnectionState::New),
+            mute: ReactiveField::new(RoomMuteState::default()),
+            peer_count: ReactiveField::new(0),
+        }
+    }
+
+    /// Returns `Stream` which yields on each modification of any field.
+    pub fn on_change(&mut self) -> impl Stream<Item = ()> {
+        futures::stream::select(
+            futures::stream::select(
+                self.connection.on_change(),
+                self.mute.on_change(),
+            ),
+            self.peer_count.on_change(),
+        )
+    }
+
+    /// Returns current values of all the fields.
+    pub fn snapshot(&self) -> RoomStateSnapshot {
+        RoomStateSnapshot {
+            connection: *self.connection.get(),
+            mute: self.mute.get().clone(),
+            peer_count: *self.peer_count.get(),
+        }
+    }
+}
+
+/// Snapshot of the [`RoomState`] returned to JS.
+#[derive(Debug, Serialize)]
+struct RoomStateSnapshot {
+    connection: ConnectionState,
+    mute: RoomMuteState,
+    peer_count: usize,
+}
+
+/// Unconfirmed transition of a [`Track`] owned by an operation.
+#[derive(Clone, Copy, Debug)]
+struct Transition {
+    /// ID of the operation which started this transition or took it over,
+    /// so only this operation may roll it back.
+    op_id: u64,
+
+    /// Last confirmed [`MuteState`] of the [`Track`], restored on rollback.
+    from: MuteState,
+}
+
+/// Error of the disallowed [`MuteState`] transition.
+#[derive(Debug)]
+struct InvalidTransition {
+    from: MuteState,
+    to: MuteState,
+}
+
+impl fmt::Display for InvalidTransition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Invalid transition from {:?} to {:?}",
+            self.from, self.to
+        )
+    }
+}
+
+/// Media track of a [`PeerConnection`], which can be muted.
+trait Track {
+    fn kind(&self) -> SenderKind;
+
+    fn direction(&self) -> MediaDirection;
+
+    fn state(&self) -> &ReactiveField<MuteState>;
+
+    fn state_mut(&mut self) -> &mut ReactiveField<MuteState>;
+
+    /// Returns unconfirmed [`Transition`] of this [`Track`] (if any).
+    fn transition_mut(&mut self) -> &mut Option<Transition>;
+
+    /// Sets `enabled` property of the underlying `MediaStreamTrack` (if any).
+    fn set_track_enabled(&self, enabled: bool);
+
+    /// Applies mute confirmed by server.
+    fn mute(&mut self) {
+        self.state_mut().set(MuteState::Muted);
+        *self.transition_mut() = None;
+        self.set_track_enabled(false);
+    }
+
+    /// Applies unmute confirmed by server.
+    fn unmute(&mut self) {
+        self.state_mut().set(MuteState::Unmuted);
+        *self.transition_mut() = None;
+        self.set_track_enabled(true);
+    }
+
+    /// Starts mute transition owned by the operation with the provided ID,
+    /// returning `Future` which resolves once mute is confirmed.
+    ///
+    /// Pending unmute (if any) is cancelled and taken over. If `optimistic`
+    /// is `true` then track is disabled right away.
+    fn on_mute(
+        &mut self,
+        op_id: u64,
+        optimistic: bool,
+    ) -> Result<When, InvalidTransition> {
+        let from = *self.state().get();
+        if from == MuteState::Unmuting {
+            self.state_mut().drop_when_subs();
+        }
+        self.set_state(MuteState::Muting)?;
+        self.own_transition(op_id, from);
+        if optimistic {
+            self.set_track_enabled(false);
+        }
+        Ok(self.state_mut().when_eq(MuteState::Muted))
+    }
+
+    /// Starts unmute transition owned by the operation with the provided
+    /// ID, returning `Future` which resolves once unmute is confirmed.
+    ///
+    /// Pending mute (if any) is cancelled and taken over.
+    fn on_unmute(&mut self, op_id: u64) -> Result<When, InvalidTransition> {
+        let from = *self.state().get();
+        if from == MuteState::Muting {
+            self.state_mut().drop_when_subs();
+        }
+        self.set_state(MuteState::Unmuting)?;
+        self.own_transition(op_id, from);
+        Ok(self.state_mut().when_eq(MuteState::Unmuted))
+    }
+
+    /// Passes unconfirmed transition of this [`Track`] to the operation with
+    /// the provided ID, keeping the last confirmed state if the transition
+    /// was started by another operation.
+    fn own_transition(&mut self, op_id: u64, from: MuteState) {
+        let transition = self.transition_mut();
+        let from = transition.map_or(from, |t| t.from);
+        *transition = Some(Transition { op_id, from });
+    }
+
+    /// Mutes this [`Track`] without server's confirmation, cancelling
+    /// pending unmute (if any).
+    fn mute_local(&mut self) {
+        if *self.state().get() == MuteState::Unmuting {
+            self.state_mut().drop_when_subs();
+        }
+        self.mute();
+    }
+
+    /// Unmutes this [`Track`] without server's confirmation, cancelling
+    /// pending mute (if any).
+    fn unmute_local(&mut self) {
+        if *self.state().get() == MuteState::Muting {
+            self.state_mut().drop_when_subs();
+        }
+        self.unmute();
+    }
+
+    /// Reverts unconfirmed transition (if any) to the last confirmed state,
+    /// if it's owned by the operation with the provided ID.
+    fn rollback(&mut self, op_id: u64) {
+        match *self.transition_mut() {
+            Some(t) if t.op_id == op_id && t.from.is_muted() => self.mute(),
+            Some(t) if t.op_id == op_id => self.unmute(),
+            _ => (),
+        }
+    }
+
+    fn is_muted(&self) -> bool {
+        self.state().get().is_muted()
+    }
+
+    /// Transits this [`Track`] into the provided [`MuteState`], if such
+    /// transition is allowed.
+    fn set_state(&mut self, to: MuteState) -> Result<(), InvalidTransition> {
+        let from = *self.state().get();
+        if !from.can_transit_to(to) {
+            return Err(InvalidTransition { from, to });
+        }
+        self.state_mut().set(to);
+        Ok(())
+    }
+}
+
+/// Outgoing media track.
+#[derive(Debug)]
+struct Sender {
+    /// ID of the underlying track.
+    id: String,
+    kind: SenderKind,
+    #[cfg(feature = "media")]
+    track: Option<web_sys::MediaStreamTrack>,
+
+    /// Transceiver sending the underlying track to the remote peer.
+    #[cfg(feature = "media")]
+    transceiver: Option<platform::Transceiver>,
+
+    /// Way the mute is applied to the underlying track.
+    #[cfg(feature = "media")]
+    mute_strategy: MuteStrategy,
+    state: ReactiveField<MuteState>,
+
+    /// Unconfirmed [`Transition`] of this [`Sender`] (if any).
+    transition: Option<Transition>,
+
+    /// Indicates whether this [`Sender`] is muted by moderator, so it can't
+    /// be unmuted until server allows it.
+    is_force_muted: bool,
+}
+
+impl Sender {
+    pub fn new<S: Into<String>>(id: S, kind: SenderKind) -> Self {
+        Self {
+            id: id.into(),
+            kind,
+            #[cfg(feature = "media")]
+            track: None,
+            #[cfg(feature = "media")]
+            transceiver: None,
+            #[cfg(feature = "media")]
+            mute_strategy: MuteStrategy::Enabled,
+            state: ReactiveField::new(MuteState::Unmuted),
+            transition: None,
+            is_force_muted: false,
+        }
+    }
+
+    /// Returns [`TrackInfo`] of this [`Sender`] of the provided peer.
+    pub fn info(&self, peer_id: PeerId) -> TrackInfo {
+        let state = *self.state.get();
+        TrackInfo {
+            track_id: self.id.clone(),
+            kind: self.kind,
+            muted: state.is_muted(),
+            busy: matches!(state, MuteState::Muting | MuteState::Unmuting),
+            peer_id: peer_id.0,
+        }
+    }
+
+    /// Replaces the underlying `MediaStreamTrack`, applying current mute
+    /// state to it, so switching devices doesn't unmute this [`Sender`].
+    ///
+    /// Returns the replaced `MediaStreamTrack` (if any).
+    #[cfg(feature = "media")]
+    pub fn replace_track(
+        &mut self,
+        track: web_sys::MediaStreamTrack,
+    ) -> Option<web_sys::MediaStreamTrack> {
+        let is_muted = self.is_muted();
+        match self.mute_strategy {
+            MuteStrategy::Enabled => {
+                track.set_enabled(!is_muted);
+                self.attach_track(Some(&track));
+            }
+            MuteStrategy::ReplaceTrack => {
+                self.attach_track(Some(&track).filter(|_| !is_muted));
+            }
+        }
+        self.track.replace(track)
+    }
+
+    /// Sets track sent by the transceiver of this [`Sender`] (if any).
+    #[cfg(feature = "media")]
+    fn attach_track(&self, track: Option<&web_sys::MediaStreamTrack>) {
+        if let Some(transceiver) = &self.transceiver {
+            let replaced = transceiver.replace_track(track);
+            spawn_local(async move {
+                if let Err(e) = replaced.await {
+                    log::error!("Failed to replace sent track: {:?}", e);
+                }
+            });
+        }
+    }
+}
+
+impl Track for Sender {
+    fn kind(&self) -> SenderKind {
+        self.kind
+    }
+
+    fn direction(&self) -> MediaDirection {
+        MediaDirection::Send
+    }
+
+    fn state(&self) -> &ReactiveField<MuteState> {
+        &self.state
+    }
+
+    fn state_mut(&mut self) -> &mut ReactiveField<MuteState> {
+        &mut self.state
+    }
+
+    fn transition_mut(&mut self) -> &mut Option<Transition> {
+        &mut self.transition
+    }
+
+    /// Does nothing if this [`Sender`] is muted by moderator.
+    fn unmute_local(&mut self) {
+        if self.is_force_muted {
+            return;
+        }
+        if *self.state.get() == MuteState::Muting {
+            self.state.drop_when_subs();
+        }
+        self.unmute();
+    }
+
+    #[cfg(feature = "media")]
+    fn set_track_enabled(&self, enabled: bool) {
+        if let Some(track) = &self.track {
+            match self.mute_strategy {
+                MuteStrategy::Enabled => track.set_enabled(enabled),
+                MuteStrategy::ReplaceTrack => {
+                    self.attach_track(Some(track).filter(|_| enabled));
+                }
+            }
+        }
+    }
+
+    /// No-op when built without `media` feature.
+    #[cfg(not(feature = "media"))]
+    fn set_track_enabled(&self, _: bool) {}
+}
+
+/// Incoming media track.
+#[derive(Debug)]
+struct Receiver {
+    kind: SenderKind,
+    state: ReactiveField<MuteState>,
+
+    /// Unconfirmed [`Transition`] of this [`Receiver`] (if any).
+    transition: Option<Transition>,
+}
+
+impl Receiver {
+    pub fn new(kind: SenderKind) -> Self {
+        Self {
+            kind,
+            state: ReactiveField::new(MuteState::Unmuted),
+            transition: None,
+        }
+    }
+}
+
+impl Track for Receiver {
+    fn kind(&self) -> SenderKind {
+        self.kind
+    }
+
+    fn direction(&self) -> MediaDirection {
+        MediaDirection::Recv
+    }
+
+    fn state(&self) -> &ReactiveField<MuteState> {
+        &self.state
+    }
+
+    fn state_mut(&mut self) -> &mut ReactiveField<MuteState> {
+        &mut self.state
+    }
+
+    fn transition_mut(&mut self) -> &mut Option<Transition> {
+        &mut self.transition
+    }
+
+    /// No-op, since remote tracks are not received yet.
+    fn set_track_enabled(&self, _: bool) {}
+}
+
+// `media` feature requires browser's WebRTC implementation, and the native
+// executor driving these tests isn't available in browser.
+#[cfg(all(
+    test,
+    feature = "test-utils",
+    not(feature = "media"),
+    not(target_arch = "wasm32"),
+))]
+mod tests {
+    use super::*;
+
+    use proptest::strategy::Strategy as _;
+
+    use crate::proto::{ClientMsg, PeerState, SenderState, ServerMsg};
+
+    /// Creates [`PeerState`] of the peer with the provided ID having unmuted
+    /// [`Sender`]s of the provided kinds, identified by their kinds.
+    fn peer(peer_id: i32, kinds: &[SenderKind]) -> PeerState {
+        PeerState {
+            peer_id,
+            senders: kinds
+                .iter()
+                .map(|kind| SenderState {
+                    track_id: format!("{:?}", kind).to_lowercase(),
+                    kind: *kind,
+                    muted: false,
+                })
+                .collect(),
+        }
+    }
+
+    /// Creates room driven by the returned [`FakeServer`] and
+    /// [`ManualTimer`], and joins it with the provided peers.
+    fn joined_room(
+        config: RoomConfig,
+        peers: Vec<PeerState>,
+    ) -> (RoomHandle, FakeServer, ManualTimer) {
+        let timer = ManualTimer::new();
+        let (room, server) =
+            RoomHandle::with_fake_server(config, Rc::new(timer.clone()));
+        server.send(Event::RoomJoined {
+            role: config.role,
+            peers,
+        });
+        run_until_stalled();
+        (room, server, timer)
+    }
+
+    /// Drives provided operation in background, returning it back, so its
+    /// outcome can be peeked.
+    fn spawn(op: SharedOperation) -> SharedOperation {
+        spawn_local(op.clone().map(drop));
+        run_until_stalled();
+        op
+    }
+
+    fn mute_cmd(media: MediaKinds) -> Command {
+        Command::MuteRoom {
+            audio: media.audio,
+            video: media.video,
+            display: media.display,
+            direction: media.direction,
+        }
+    }
+
+    fn unmute_cmd(media: MediaKinds) -> Command {
+        Command::UnmuteRoom {
+            audio: media.audio,
+            video: media.video,
+            display: media.display,
+            direction: media.direction,
+        }
+    }
+
+    fn muted(media: MediaKinds) -> Event {
+        Event::RoomMuted {
+            audio: media.audio,
+            video: media.video,
+            display: media.display,
+            direction: media.direction,
+        }
+    }
+
+    fn unmuted(media: MediaKinds) -> Event {
+        Event::RoomUnmuted {
+            audio: media.audio,
+            video: media.video,
+            display: media.display,
+            direction: media.direction,
+        }
+    }
+
+    #[test]
+    fn mute_is_sent_when_some_peer_has_no_such_tracks() {
+        let config = RoomConfig::default();
+        let peers = vec![
+            peer(1, &[SenderKind::Audio, SenderKind::Video]),
+            peer(2, &[SenderKind::Video]),
+        ];
+        let (room, server, _timer) = joined_room(config, peers);
+        let audio = MediaKinds::new(true, false);
+
+        let op = spawn(room.inner_mute(audio, config.mute_timeout));
+        assert_eq!(server.take_sent(), vec![mute_cmd(audio)]);
+
+        server.send(muted(audio));
+        run_until_stalled();
+        assert!(matches!(op.peek(), Some(Ok(_))));
+        assert_eq!(
+            room.sender_state(1, SenderKind::Audio),
+            Some(MuteState::Muted)
+        );
+    }
+
+    #[test]
+    fn superseded_mute_doesnt_roll_back_tracks_of_superseding_unmute() {
+        let config = RoomConfig {
+            max_attempts: 1,
+            ..RoomConfig::default()
+        };
+        let peers = vec![peer(1, &[SenderKind::Audio, SenderKind::Video])];
+        let (room, _server, timer) = joined_room(config, peers);
+
+        let mute = spawn(
+            room.inner_mute(MediaKinds::new(true, true), config.mute_timeout),
+        );
+        let unmute =
+            spawn(room.inner_unmute(
+                MediaKinds::new(true, false),
+                config.unmute_timeout,
+            ));
+        assert_eq!(
+            room.sender_state(1, SenderKind::Audio),
+            Some(MuteState::Unmuting),
+        );
+
+        timer.advance(config.mute_timeout.max(config.unmute_timeout));
+        run_until_stalled();
+
+        assert!(matches!(mute.peek(), Some(Err(_))));
+        assert!(matches!(unmute.peek(), Some(Err(_))));
+        assert_eq!(
+            room.sender_state(1, SenderKind::Audio),
+            Some(MuteState::Unmuted),
+        );
+        assert_eq!(
+            room.sender_state(1, SenderKind::Video),
+            Some(MuteState::Unmuted),
+        );
+        assert_eq!(room.pending_operations(), 0);
+    }
+
+    #[test]
+    fn mute_is_superseded_once_all_its_callers_detach() {
+        let config = RoomConfig::default();
+        let peers = vec![peer(1, &[SenderKind::Audio])];
+        let (room, _server, _timer) = joined_room(config, peers);
+        let media = MediaKinds::new(true, false);
+        let key = OperationKey {
+            kind: OperationKind::Mute,
+            media,
+            target: OperationTarget::Room,
+        };
+
+        let first = spawn(room.inner_mute(media, config.mute_timeout));
+        let second = spawn(room.inner_mute(media, config.mute_timeout));
+        let id = room.0.borrow().pending_ops[&key].id;
+
+        room.detach(key, id);
+        run_until_stalled();
+        assert_eq!(
+            room.sender_state(1, SenderKind::Audio),
+            Some(MuteState::Muting),
+        );
+        assert!(second.peek().is_none());
+
+        room.detach(key, id);
+        run_until_stalled();
+        assert_eq!(
+            room.sender_state(1, SenderKind::Audio),
+            Some(MuteState::Unmuting),
+        );
+        assert!(matches!(first.peek(), Some(Err(_))));
+    }
+
+    #[test]
+    fn unconfirmed_mute_of_peers_is_rolled_back() {
+        let config = RoomConfig {
+            role: Role::Moderator,
+            max_attempts: 1,
+            ..RoomConfig::default()
+        };
+        let kinds = [SenderKind::Audio, SenderKind::Video];
+        let peers = vec![peer(1, &kinds), peer(2, &kinds), peer(3, &kinds)];
+        let (room, server, timer) = joined_room(config, peers);
+
+        let op = spawn(room.inner_mute_all_except(vec![1]));
+        assert_eq!(
+            room.sender_state(3, SenderKind::Video),
+            Some(MuteState::Muting),
+        );
+        server.send(Event::PeerMuted {
+            peer_id: 2,
+            audio: true,
+            video: true,
+        });
+        timer.advance(config.mute_timeout);
+        run_until_stalled();
+
+        let report = PeersMuteReport::from(op.peek().unwrap().clone().unwrap());
+        assert_eq!(report.muted, vec![2]);
+        assert_eq!(report.failed, vec![3]);
+        for (peer_id, state) in [
+            (1, MuteState::Unmuted),
+            (2, MuteState::Muted),
+            (3, MuteState::Unmuted),
+        ] {
+            for kind in kinds {
+                assert_eq!(room.sender_state(peer_id, kind), Some(state));
+            }
+        }
+        assert_eq!(room.pending_operations(), 0);
+    }
+
+    #[test]
+    fn operation_is_rejected_once_room_is_closed_before_join() {
+        let config = RoomConfig::default();
+        let (room, _server) =
+            RoomHandle::with_fake_server(config, Rc::new(ManualTimer::new()));
+        room.close();
+        run_until_stalled();
+
+        let op = spawn(room.inner_mute(MediaKinds::new(true, false), 0));
+        let err = op.peek().unwrap().clone().unwrap_err();
+        assert_eq!(err.kind(), RoomErrorKind::Closed);
+        assert_eq!(room.pending_operations(), 0);
+    }
+
+    #[test]
+    fn event_task_finishes_once_room_is_closed() {
+        let peers = vec![peer(1, &[SenderKind::Audio])];
+        let (room, server, _timer) = joined_room(RoomConfig::default(), peers);
+        let weak_refs = Rc::weak_count(&room.0);
+
+        room.close();
+        run_until_stalled();
+
+        // Only the event task's reference is released.
+        assert_eq!(Rc::weak_count(&room.0), weak_refs - 1);
+        server.send(muted(MediaKinds::new(true, false)));
+        run_until_stalled();
+        assert_eq!(room.snapshot().connection, ConnectionState::Closed);
+        assert!(room.snapshot().tracks.is_empty());
+    }
+
+    #[test]
+    fn calls_are_performed_by_room_task() {
+        let peers = vec![peer(1, &[SenderKind::Audio])];
+        let (room, _server, _timer) = joined_room(RoomConfig::default(), peers);
+
+        // Calls never borrow the room, so may be made while it's borrowed.
+        let borrowed = room.0.borrow();
+        room.mute_local(true, false);
+        drop(borrowed);
+        assert_eq!(
+            room.sender_state(1, SenderKind::Audio),
+            Some(MuteState::Unmuted),
+        );
+        run_until_stalled();
+        assert_eq!(
+            room.sender_state(1, SenderKind::Audio),
+            Some(MuteState::Muted),
+        );
+
+        let borrowed = room.0.borrow();
+        room.close();
+        room.unmute_local(true, false);
+        drop(borrowed);
+        run_until_stalled();
+        assert_eq!(room.snapshot().connection, ConnectionState::Closed);
+    }
+
+    #[test]
+    fn event_task_finishes_once_room_is_dropped() {
+        let peers = vec![peer(1, &[SenderKind::Audio])];
+        let (room, server, _timer) = joined_room(RoomConfig::default(), peers);
+        let weak_room = Rc::downgrade(&room.0);
+
+        drop(room);
+        run_until_stalled();
+
+        assert!(weak_room.upgrade().is_none());
+        server.send(Event::RoomState { peers: Vec::new() });
+        run_until_stalled();
+    }
+
+    #[test]
+    fn latencies_of_concurrent_operations_are_recorded_separately() {
+        let config = RoomConfig::default();
+        let peers = vec![peer(1, &[SenderKind::Audio, SenderKind::Video])];
+        let (room, server, _timer) = joined_room(config, peers);
+        let audio = MediaKinds::new(true, false);
+        let video = MediaKinds::new(false, true);
+
+        let ops = [
+            spawn(room.inner_mute(audio, config.mute_timeout)),
+            spawn(room.inner_mute(video, config.mute_timeout)),
+        ];
+        assert_eq!(server.take_sent(), vec![mute_cmd(audio), mute_cmd(video)]);
+        server.send(muted(audio));
+        server.send(muted(video));
+        run_until_stalled();
+
+        assert!(ops.iter().all(|op| matches!(op.peek(), Some(Ok(_)))));
+        let latencies =
+            serde_json::to_value(room.0.borrow().stats.latencies()).unwrap();
+        assert_eq!(latencies["mute"]["count"], 2);
+    }
+
+    #[test]
+    fn stats_are_gathered_periodically() {
+        let config = RoomConfig::default();
+        let peers = vec![peer(1, &[SenderKind::Audio])];
+        let (room, server, timer) = joined_room(config, peers);
+        let audio = MediaKinds::new(true, false);
+        let collected =
+            || serde_json::to_value(room.0.borrow().stats.collected()).unwrap();
+
+        let op = spawn(room.inner_mute(audio, config.mute_timeout));
+        server.send(muted(audio));
+        run_until_stalled();
+        assert!(matches!(op.peek(), Some(Ok(_))));
+        assert_eq!(collected(), serde_json::json!([]));
+
+        timer.advance(config.stats_interval);
+        run_until_stalled();
+        assert_eq!(collected()[0]["peer_id"], 1);
+        assert_eq!(collected()[0]["mutes"], 1);
+    }
+
+    #[test]
+    fn dropped_command_is_resent() {
+        let config = RoomConfig {
+            max_attempts: 2,
+            ..RoomConfig::default()
+        };
+        let peers = vec![peer(1, &[SenderKind::Audio])];
+        let (room, server, timer) = joined_room(config, peers);
+        let audio = MediaKinds::new(true, false);
+
+        server.faults().drop_command(0);
+        let op = spawn(room.inner_mute(audio, config.mute_timeout));
+        assert!(server.take_sent().is_empty());
+
+        timer.advance(config.mute_timeout);
+        run_until_stalled();
+        timer.advance(config.retry_backoff);
+        run_until_stalled();
+        assert_eq!(server.take_sent(), vec![mute_cmd(audio)]);
+
+        server.send(muted(audio));
+        run_until_stalled();
+        assert!(matches!(op.peek(), Some(Ok(_))));
+    }
+
+    #[test]
+    fn duplicated_event_is_delivered_twice() {
+        let (server, mut client) = FakeServer::new(Rc::new(ManualTimer::new()));
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let seqs = Rc::clone(&received);
+        client.on_message(move |msg| seqs.borrow_mut().push(msg.seq));
+
+        server.faults().duplicate_next_event();
+        server.send(Event::RoomHeld);
+        server.send(Event::RoomResumed);
+
+        assert_eq!(*received.borrow(), vec![0, 0, 1]);
+    }
+
+    #[test]
+    fn events_are_numbered_per_room() {
+        let timer = ManualTimer::new();
+        let ws = Rc::new(RefCell::new(WebSocket::with_timer(Rc::new(
+            timer.clone(),
+        ))));
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let clients: Vec<_> = ["first", "second"]
+            .iter()
+            .map(|id| {
+                let mut client =
+                    RoomClient::new(RoomId((*id).to_owned()), Rc::clone(&ws));
+                let received = Rc::clone(&received);
+                client.on_message(move |msg| {
+                    received.borrow_mut().push((msg.room_id.0, msg.seq));
+                });
+                client
+            })
+            .collect();
+
+        for client in &clients {
+            client.send(Command::HoldRoom);
+        }
+        clients[1].send(Command::ResumeRoom);
+        timer.advance(3000);
+        run_until_stalled();
+
+        let mut received = received.borrow().clone();
+        received.sort();
+        assert_eq!(
+            received,
+            vec![
+                ("first".to_owned(), 0),
+                ("second".to_owned(), 0),
+                ("second".to_owned(), 1),
+            ],
+        );
+    }
+
+    #[test]
+    fn queued_operation_is_rejected_once_connect_fails() {
+        let config = RoomConfig::default();
+        let timer = ManualTimer::new();
+        let (room, _server) =
+            RoomHandle::with_fake_server(config, Rc::new(timer.clone()));
+        let audio = MediaKinds::new(true, false);
+
+        let connect = |url: &'static str| {
+            let room = room.clone();
+            let fut = async move {
+                room.inner_connect(Some(url), "token".to_owned()).await
+            }
+            .boxed_local()
+            .shared();
+            spawn_local(fut.clone().map(drop));
+            fut
+        };
+        let op = spawn(room.inner_mute(audio, config.mute_timeout));
+        let failed = connect("http://localhost");
+        let retry = connect("ws://localhost");
+        run_until_stalled();
+        timer.advance(100);
+        run_until_stalled();
+
+        fn kind<T>(res: &Result<T, RoomError>) -> Option<RoomErrorKind> {
+            res.as_ref().err().map(RoomError::kind)
+        }
+        assert_eq!(retry.peek().map(kind), Some(Some(RoomErrorKind::Busy)));
+        assert_eq!(
+            failed.peek().map(kind),
+            Some(Some(RoomErrorKind::Disconnected)),
+        );
+        assert_eq!(
+            op.peek().map(kind),
+            Some(Some(RoomErrorKind::Disconnected)),
+        );
+        assert_eq!(room.pending_operations(), 0);
+        assert_eq!(room.snapshot().connection, ConnectionState::New);
+    }
+
+    #[test]
+    fn hold_and_resume_are_signalled_once_confirmed() {
+        let peers =
+            vec![peer(1, &[SenderKind::Audio]), peer(2, &[SenderKind::Audio])];
+        let (room, server, _timer) = joined_room(RoomConfig::default(), peers);
+        let audio_of_2 =
+            room.mute_track(PeerId(2), "audio".to_owned()).shared();
+        spawn_local(audio_of_2.clone().map(drop));
+        run_until_stalled();
+        server.take_sent();
+        server.send(Event::TrackMuted {
+            peer_id: 2,
+            track_id: "audio".to_owned(),
+        });
+        run_until_stalled();
+        assert!(matches!(audio_of_2.peek(), Some(Ok(()))));
+
+        let hold = room.inner_hold().shared();
+        spawn_local(hold.clone().map(drop));
+        run_until_stalled();
+        assert_eq!(server.take_sent(), vec![mute_cmd(MediaKinds::all())]);
+        server.send(muted(MediaKinds::all()));
+        run_until_stalled();
+        assert_eq!(server.take_sent(), vec![Command::HoldRoom]);
+        assert!(!room.is_on_hold());
+        server.send(Event::RoomHeld);
+        run_until_stalled();
+        assert!(matches!(hold.peek(), Some(Ok(Some(_)))));
+        assert!(room.is_on_hold());
+
+        let resume = room.inner_resume().shared();
+        spawn_local(resume.clone().map(drop));
+        run_until_stalled();
+        let audio = MediaKinds::new(true, false);
+        let recv = MediaKinds::all().with_direction(MediaDirection::Recv);
+        let unmutes = server.take_sent();
+        assert_eq!(unmutes.len(), 2);
+        assert!(unmutes.contains(&unmute_cmd(audio)));
+        server.send(unmuted(audio));
+        server.send(unmuted(recv));
+        run_until_stalled();
+        assert_eq!(server.take_sent(), vec![Command::ResumeRoom]);
+        server.send(Event::RoomResumed);
+        run_until_stalled();
+        assert!(!room.is_on_hold());
+        assert_eq!(
+            server.take_sent(),
+            vec![Command::MuteTrack {
+                peer_id: 2,
+                track_id: "audio".to_owned(),
+            }],
+        );
+        server.send(Event::TrackMuted {
+            peer_id: 2,
+            track_id: "audio".to_owned(),
+        });
+        run_until_stalled();
+        assert!(matches!(resume.peek(), Some(Ok(()))));
+        assert_eq!(
+            room.sender_state(1, SenderKind::Audio),
+            Some(MuteState::Unmuted),
+        );
+        assert_eq!(
+            room.sender_state(2, SenderKind::Audio),
+            Some(MuteState::Muted),
+        );
+    }
+
+    #[test]
+    fn unconfirmed_hold_is_undone() {
+        let peers = vec![peer(1, &[SenderKind::Audio])];
+        let (room, server, timer) = joined_room(RoomConfig::default(), peers);
+
+        let hold = room.inner_hold().shared();
+        spawn_local(hold.clone().map(drop));
+        run_until_stalled();
+        server.take_sent();
+        server.send(muted(MediaKinds::all()));
+        run_until_stalled();
+        assert_eq!(server.take_sent(), vec![Command::HoldRoom]);
+        timer.advance(RoomConfig::default().mute_timeout);
+        run_until_stalled();
+
+        let err = hold.peek().unwrap().clone().unwrap_err();
+        assert_eq!(err.kind(), RoomErrorKind::Timeout);
+        assert_eq!(server.take_sent(), vec![Command::ResumeRoom]);
+        assert!(!room.is_on_hold());
+    }
+
+    #[test]
+    fn closed_client_doesnt_close_room_with_same_id() {
+        let timer = ManualTimer::new();
+        let ws = Rc::new(RefCell::new(WebSocket::with_timer(Rc::new(timer))));
+        let room_id = RoomId("room".to_owned());
+
+        let mut closed = RoomClient::new(room_id.clone(), Rc::clone(&ws));
+        closed.on_message(drop);
+        closed.close();
+        assert!(!ws.borrow().has_room(&room_id));
+        let mut joined = RoomClient::new(room_id.clone(), Rc::clone(&ws));
+        joined.on_message(drop);
+        drop(closed);
+
+        assert!(ws.borrow().has_room(&room_id));
+        drop(joined);
+        assert!(!ws.borrow().has_room(&room_id));
+    }
+
+    #[test]
+    fn faults_are_scoped_per_room() {
+        let config = RoomConfig::default();
+        let timer = ManualTimer::new();
+        let ws = Rc::new(RefCell::new(WebSocket::with_timer(Rc::new(
+            timer.clone(),
+        ))));
+        let rooms: Vec<_> = ["first", "second"]
+            .iter()
+            .map(|id| {
+                let client =
+                    RoomClient::new(RoomId((*id).to_owned()), Rc::clone(&ws));
+                let room = RoomHandle::new_with_client(
+                    client,
+                    config,
+                    Rc::new(timer.clone()),
+                );
+                let joined = room.clone();
+                spawn_local(async move {
+                    joined.join("token".to_owned()).await.unwrap();
+                });
+                room
+            })
+            .collect();
+        run_until_stalled();
+        // Hello handshake and join are answered one after another.
+        for _ in 0..2 {
+            timer.advance(3000);
+            run_until_stalled();
+        }
+
+        let faults = rooms[0].0.borrow().ws.faults();
+        faults.close();
+        let audio = MediaKinds::new(true, false);
+        let ops: Vec<_> = rooms
+            .iter()
+            .map(|room| spawn(room.inner_mute(audio, config.mute_timeout)))
+            .collect();
+        timer.advance(3000);
+        run_until_stalled();
+
+        assert!(ops[0].peek().is_none());
+        assert!(matches!(ops[1].peek(), Some(Ok(_))));
+        assert_eq!(
+            rooms[1].sender_state(100, SenderKind::Audio),
+            Some(MuteState::Muted),
+        );
+    }
+
+    #[test]
+    fn fake_server_collects_commands_of_each_step() {
+        let config = RoomConfig::default();
+        let peers = vec![peer(1, &[SenderKind::Audio])];
+        let (room, server, _timer) = joined_room(config, peers);
+        let audio = MediaKinds::new(true, false);
+
+        let mute = spawn(room.inner_mute(audio, config.mute_timeout));
+        assert_eq!(server.take_sent(), vec![mute_cmd(audio)]);
+        server.send(muted(audio));
+        run_until_stalled();
+        assert!(matches!(mute.peek(), Some(Ok(_))));
+
+        let unmute = spawn(room.inner_unmute(audio, config.unmute_timeout));
+        assert_eq!(server.take_sent(), vec![unmute_cmd(audio)],);
+        server.send(unmuted(audio));
+        run_until_stalled();
+        assert!(matches!(unmute.peek(), Some(Ok(_))));
+        assert!(server.take_sent().is_empty());
+    }
+
+    #[test]
+    fn introspection_reflects_room_state() {
+        let config = RoomConfig::default();
+        let timer = ManualTimer::new();
+        let (room, server) =
+            RoomHandle::with_fake_server(config, Rc::new(timer.clone()));
+        assert_eq!(room.peer_count(), 0);
+        assert_eq!(room.sender_state(1, SenderKind::Audio), None);
+
+        server.send(Event::RoomJoined {
+            role: Role::Member,
+            peers: vec![
+                peer(1, &[SenderKind::Audio]),
+                peer(2, &[SenderKind::Audio, SenderKind::Video]),
+            ],
+        });
+        run_until_stalled();
+        assert_eq!(room.peer_count(), 2);
+        assert_eq!(room.sender_state(1, SenderKind::Video), None);
+
+        let audio = MediaKinds::new(true, false);
+        let _op = spawn(room.inner_mute(audio, config.mute_timeout));
+        assert_eq!(room.pending_operations(), 1);
+        assert_eq!(
+            room.sender_state(2, SenderKind::Audio),
+            Some(MuteState::Muting),
+        );
+
+        server.send(muted(audio));
+        run_until_stalled();
+        assert_eq!(room.pending_operations(), 0);
+        assert_eq!(
+            room.sender_state(2, SenderKind::Audio),
+            Some(MuteState::Muted),
+        );
+        assert_eq!(
+            room.sender_state(2, SenderKind::Video),
+            Some(MuteState::Unmuted),
+        );
+    }
+
+    /// Creates [`TrackInfo`] of the [`Sender`] created via [`peer`].
+    fn track(peer_id: i32, kind: SenderKind, state: MuteState) -> TrackInfo {
+        let mut sender =
+            Sender::new(format!("{:?}", kind).to_lowercase(), kind);
+        sender.state.set(state);
+        sender.info(PeerId(peer_id))
+    }
+
+    #[test]
+    fn snapshots_of_partially_confirmed_mute() {
+        let config = RoomConfig {
+            max_attempts: 1,
+            ..RoomConfig::default()
+        };
+        let peers = vec![peer(1, &[SenderKind::Audio, SenderKind::Video])];
+        let (room, server, timer) = joined_room(config, peers);
+        let both = MediaKinds::new(true, true);
+        let audio = MediaKinds::new(true, false);
+
+        let op = spawn(room.inner_mute(both, config.mute_timeout));
+        server.send(muted(audio));
+        run_until_stalled();
+        assert_eq!(
+            room.snapshot(),
+            RoomSnapshot {
+                connection: ConnectionState::Connected,
+                role: Role::Member,
+                tracks: vec![
+                    track(1, SenderKind::Audio, MuteState::Muted),
+                    track(1, SenderKind::Video, MuteState::Muting),
+                ],
+                pending_ops: vec![(OperationKind::Mute, both)],
+                remote_mutes: Vec::new(),
+            },
+        );
+
+        timer.advance(config.mute_timeout);
+        run_until_stalled();
+        assert!(op.peek().is_none());
+        assert_eq!(
+            room.snapshot(),
+            RoomSnapshot {
+                connection: ConnectionState::Connected,
+                role: Role::Member,
+                tracks: vec![
+                    track(1, SenderKind::Audio, MuteState::Unmuting),
+                    track(1, SenderKind::Video, MuteState::Unmuted),
+                ],
+                pending_ops: vec![(OperationKind::Mute, both)],
+                remote_mutes: Vec::new(),
+            },
+        );
+
+        server.send(unmuted(audio));
+        run_until_stalled();
+        let err = match op.peek() {
+            Some(Err(err)) => err,
+            res => panic!("Unexpected result: {:?}", res),
+        };
+        assert_eq!(err.kind(), RoomErrorKind::RolledBack);
+        assert_eq!(
+            room.snapshot(),
+            RoomSnapshot {
+                connection: ConnectionState::Connected,
+                role: Role::Member,
+                tracks: vec![
+                    track(1, SenderKind::Audio, MuteState::Unmuted),
+                    track(1, SenderKind::Video, MuteState::Unmuted),
+                ],
+                pending_ops: Vec::new(),
+                remote_mutes: Vec::new(),
+            },
+        );
+        assert_eq!(server.take_sent(), vec![mute_cmd(both), unmute_cmd(audio)]);
+    }
+
+    #[test]
+    fn unconfirmed_rollback_of_mute_leaves_tracks_muted() {
+        let config = RoomConfig {
+            max_attempts: 1,
+            ..RoomConfig::default()
+        };
+        let peers = vec![peer(1, &[SenderKind::Audio, SenderKind::Video])];
+        let (room, server, timer) = joined_room(config, peers);
+        let both = MediaKinds::new(true, true);
+        let audio = MediaKinds::new(true, false);
+
+        let op = spawn(room.inner_mute(both, config.mute_timeout));
+        server.send(muted(audio));
+        run_until_stalled();
+        timer.advance(config.mute_timeout);
+        run_until_stalled();
+        timer.advance(config.unmute_timeout);
+        run_until_stalled();
+
+        let err = op.peek().unwrap().clone().unwrap_err();
+        assert_eq!(err.kind(), RoomErrorKind::Timeout);
+        assert_eq!(
+            room.sender_state(1, SenderKind::Audio),
+            Some(MuteState::Muted),
+        );
+        assert_eq!(
+            room.sender_state(1, SenderKind::Video),
+            Some(MuteState::Unmuted),
+        );
+        assert_eq!(room.pending_operations(), 0);
+    }
+
+    #[test]
+    fn snapshot_of_reconciled_room_state() {
+        let config = RoomConfig::default();
+        let peers = vec![
+            peer(1, &[SenderKind::Audio, SenderKind::Video]),
+            peer(2, &[SenderKind::Audio]),
+        ];
+        let (room, server, _timer) = joined_room(config, peers);
+        let video = MediaKinds::new(false, true);
+
+        let op = spawn(room.inner_mute(video, config.mute_timeout));
+        let mut first = peer(1, &[SenderKind::Video, SenderKind::Display]);
+        first.senders[0].muted = true;
+        server.send(Event::RoomState {
+            peers: vec![first, peer(3, &[SenderKind::Audio])],
+        });
+        server.send(Event::MemberMuted {
+            peer_id: 7,
+            audio: true,
+            video: false,
+        });
+        run_until_stalled();
+
+        assert!(matches!(op.peek(), Some(Ok(_))));
+        assert_eq!(
+            room.snapshot(),
+            RoomSnapshot {
+                connection: ConnectionState::Connected,
+                role: Role::Member,
+                tracks: vec![
+                    track(1, SenderKind::Display, MuteState::Unmuted),
+                    track(1, SenderKind::Video, MuteState::Muted),
+                    track(3, SenderKind::Audio, MuteState::Unmuted),
+                ],
+                pending_ops: Vec::new(),
+                remote_mutes: vec![RemoteMuteState {
+                    peer_id: 7,
+                    audio: true,
+                    video: false,
+                }],
+            },
+        );
+    }
+
+    #[test]
+    fn mute_is_confirmed_by_room_state_after_reconnect() {
+        let config = RoomConfig::default();
+        let peers = vec![peer(1, &[SenderKind::Audio, SenderKind::Video])];
+        let (room, server, _timer) = joined_room(config, peers);
+        let faults = server.faults();
+        let audio = MediaKinds::new(true, false);
+
+        let op = spawn(room.inner_mute(audio, config.mute_timeout));
+        assert_eq!(server.take_sent(), vec![mute_cmd(audio)]);
+        faults.close();
+        server.send(muted(audio));
+        run_until_stalled();
+        assert!(op.peek().is_none());
+        assert_eq!(
+            room.snapshot().tracks,
+            vec![
+                track(1, SenderKind::Audio, MuteState::Muting),
+                track(1, SenderKind::Video, MuteState::Unmuted),
+            ],
+        );
+
+        faults.reconnect();
+        let mut state = peer(1, &[SenderKind::Audio, SenderKind::Video]);
+        state.senders[0].muted = true;
+        server.send(Event::RoomState { peers: vec![state] });
+        run_until_stalled();
+
+        assert!(matches!(op.peek(), Some(Ok(_))));
+        assert_eq!(
+            room.snapshot(),
+            RoomSnapshot {
+                connection: ConnectionState::Connected,
+                role: Role::Member,
+                tracks: vec![
+                    track(1, SenderKind::Audio, MuteState::Muted),
+                    track(1, SenderKind::Video, MuteState::Unmuted),
+                ],
+                pending_ops: Vec::new(),
+                remote_mutes: Vec::new(),
+            },
+        );
+    }
+
+    #[test]
+    fn lost_mute_is_cancelled_by_room_state_after_reconnect() {
+        let config = RoomConfig::default();
+        let peers = vec![peer(1, &[SenderKind::Audio])];
+        let (room, server, _timer) = joined_room(config, peers);
+        let faults = server.faults();
+        let audio = MediaKinds::new(true, false);
+
+        faults.close();
+        let op = spawn(room.inner_mute(audio, config.mute_timeout));
+        assert!(server.take_sent().is_empty());
+
+        faults.reconnect();
+        server.send(Event::RoomState {
+            peers: vec![peer(1, &[SenderKind::Audio])],
+        });
+        run_until_stalled();
+
+        let err = match op.peek() {
+            Some(Err(err)) => err,
+            res => panic!("Unexpected result: {:?}", res),
+        };
+        assert_eq!(err.kind(), RoomErrorKind::Cancelled);
+        assert_eq!(
+            room.snapshot(),
+            RoomSnapshot {
+                connection: ConnectionState::Connected,
+                role: Role::Member,
+                tracks: vec![track(1, SenderKind::Audio, MuteState::Unmuted)],
+                pending_ops: Vec::new(),
+                remote_mutes: Vec::new(),
+            },
+        );
+    }
+
+    /// Step of a scripted interleaving run by the [`simulate`].
+    #[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+    enum Step {
+        /// User mutes audio and/or video.
+        Mute { audio: bool, video: bool },
+
+        /// User unmutes audio and/or video.
+        Unmute { audio: bool, video: bool },
+
+        /// Server answers the oldest unanswered command.
+        Answer,
+
+        /// Server delivers its next answer twice.
+        Duplicate,
+
+        /// Time passes, so all the started operations time out.
+        Timeout,
+    }
+
+    /// Rearranges provided [`Step`]s into the next lexicographical
+    /// permutation, returning `false` if they are already the last one.
+    fn next_permutation(steps: &mut [Step]) -> bool {
+        let i = match (1..steps.len()).rev().find(|&i| steps[i - 1] < steps[i])
+        {
+            Some(i) => i,
+            None => return false,
+        };
+        let j = (i..steps.len()).rev().find(|&j| steps[i - 1] < steps[j]);
+        steps.swap(i - 1, j.unwrap());
+        steps[i..].reverse();
+        true
+    }
+
+    /// Simulated server, answering commands of a room in order.
+    struct Server {
+        fake: FakeServer,
+
+        /// Commands sent by the room, which are not answered yet.
+        unanswered: Vec<Command>,
+
+        /// Kinds of media which mute or unmute was answered.
+        answered: MediaKinds,
+
+        /// Mute state of the audio and video applied by server.
+        muted: (bool, bool),
+    }
+
+    impl Server {
+        /// Collects commands sent by the room since the previous call.
+        fn receive(&mut self) {
+            self.unanswered.extend(self.fake.take_sent());
+        }
+
+        /// Answers the oldest unanswered command (if any).
+        fn answer(&mut self) {
+            if self.unanswered.is_empty() {
+                return;
+            }
+            let (event, media, is_muted) = match self.unanswered.remove(0) {
+                Command::MuteRoom {
+                    audio,
+                    video,
+                    display,
+                    direction,
+                } => {
+                    let media = MediaKinds {
+                        audio,
+                        video,
+                        display,
+                        direction,
+                    };
+                    (muted(media), media, true)
+                }
+                Command::UnmuteRoom {
+                    audio,
+                    video,
+                    display,
+                    direction,
+                } => {
+                    let media = MediaKinds {
+                        audio,
+                        video,
+                        display,
+                        direction,
+                    };
+                    (unmuted(media), media, false)
+                }
+                _ => return,
+            };
+            if media.audio {
+                self.muted.0 = is_muted;
+            }
+            if media.video {
+                self.muted.1 = is_muted;
+            }
+            self.answered.merge(media);
+            self.fake.send(event);
+        }
+    }
+
+    /// Runs provided [`Step`]s against a fresh room, asserting that:
+    /// - no operation hangs once all of them time out;
+    /// - media, which mute or unmute wasn't answered, is rolled back to the
+    ///   initial state;
+    /// - room ends up in the server's state once all commands are answered.
+    fn simulate(steps: &[Step]) {
+        let config = RoomConfig {
+            max_attempts: 1,
+            ..RoomConfig::default()
+        };
+        let peers = vec![peer(1, &[SenderKind::Audio, SenderKind::Video])];
+        let (room, fake, timer) = joined_room(config, peers);
+        let mut server = Server {
+            fake,
+            unanswered: Vec::new(),
+            answered: MediaKinds::default(),
+            muted: (false, false),
+        };
+        let mut ops = Vec::new();
+        for step in steps {
+            match *step {
+                Step::Mute { audio, video } => {
+                    ops.push(spawn(room.inner_mute(
+                        MediaKinds::new(audio, video),
+                        config.mute_timeout,
+                    )))
+                }
+                Step::Unmute { audio, video } => {
+                    ops.push(spawn(room.inner_unmute(
+                        MediaKinds::new(audio, video),
+                        config.unmute_timeout,
+                    )))
+                }
+                Step::Answer => server.answer(),
+                Step::Duplicate => server.fake.faults().duplicate_next_event(),
+                Step::Timeout => timer.advance(config.mute_timeout),
+            }
+            run_until_stalled();
+            server.receive();
+        }
+        // Queued operations start only once the preceding ones complete.
+        for _ in 0..ops.len() {
+            timer.advance(config.mute_timeout);
+            run_until_stalled();
+            server.receive();
+        }
+
+        assert!(
+            ops.iter().all(|op| op.peek().is_some()),
+            "{:?}: operation hangs",
+            steps,
+        );
+        assert_eq!(room.pending_operations(), 0, "{:?}", steps);
+        for (kind, is_answered) in [
+            (SenderKind::Audio, server.answered.audio),
+            (SenderKind::Video, server.answered.video),
+        ] {
+            let state = room.sender_state(1, kind);
+            if !is_answered {
+                assert_eq!(state, Some(MuteState::Unmuted), "{:?}", steps);
+            }
+        }
+
+        while !server.unanswered.is_empty() {
+            server.answer();
+            run_until_stalled();
+            server.receive();
+        }
+        let to_state = |is_muted| {
+            Some(if is_muted {
+                MuteState::Muted
+            } else {
+                MuteState::Unmuted
+            })
+        };
+        assert_eq!(
+            (
+                room.sender_state(1, SenderKind::Audio),
+                room.sender_state(1, SenderKind::Video),
+            ),
+            (to_state(server.muted.0), to_state(server.muted.1)),
+            "{:?}",
+            steps,
+        );
+    }
+
+    /// Runs all the distinct interleavings of the provided [`Step`]s.
+    fn simulate_interleavings(mut steps: Vec<Step>) {
+        steps.sort();
+        loop {
+            simulate(&steps);
+            if !next_permutation(&mut steps) {
+                break;
+            }
+        }
+    }
+
+    #[test]
+    fn interleavings_of_overlapping_mute_and_unmute() {
+        simulate_interleavings(vec![
+            Step::Mute {
+                audio: true,
+                video: true,
+            },
+            Step::Unmute {
+                audio: true,
+                video: false,
+            },
+            Step::Answer,
+            Step::Answer,
+            Step::Timeout,
+        ]);
+    }
+
+    #[test]
+    fn interleavings_of_repeated_mute() {
+        simulate_interleavings(vec![
+            Step::Mute {
+                audio: true,
+                video: false,
+            },
+            Step::Unmute {
+                audio: true,
+                video: false,
+            },
+            Step::Mute {
+                audio: true,
+                video: false,
+            },
+            Step::Answer,
+            Step::Answer,
+            Step::Timeout,
+        ]);
+    }
+
+    #[test]
+    fn interleavings_of_crossing_operations() {
+        simulate_interleavings(vec![
+            Step::Mute {
+                audio: true,
+                video: true,
+            },
+            Step::Unmute {
+                audio: false,
+                video: true,
+            },
+            Step::Mute {
+                audio: true,
+                video: false,
+            },
+            Step::Answer,
+            Step::Timeout,
+        ]);
+    }
+
+    /// Returns [`Strategy`] generating random [`Step`]s.
+    ///
+    /// [`Strategy`]: proptest::strategy::Strategy
+    fn step() -> impl proptest::strategy::Strategy<Value = Step> {
+        use proptest::prelude::*;
+
+        prop_oneof![
+            any::<(bool, bool)>()
+                .prop_map(|(audio, video)| Step::Mute { audio, video }),
+            any::<(bool, bool)>()
+                .prop_map(|(audio, video)| Step::Unmute { audio, video }),
+            Just(Step::Answer),
+            Just(Step::Duplicate),
+            Just(Step::Timeout),
+        ]
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn random_interleavings(
+            steps in proptest::collection::vec(step(), 1..16),
+        ) {
+            simulate(&steps);
+        }
+    }
+
+    /// Measures wall time of mute and unmute confirmed via [`FakeServer`] in
+    /// rooms of 1, 10, 100 and 1000 peers.
+    ///
+    /// Run it via `make bench`.
+    #[test]
+    #[ignore]
+    fn mute_latency_vs_room_size() {
+        const ROUNDS: u32 = 20;
+
+        let config = RoomConfig::default();
+        let media = MediaKinds::new(true, true);
+        for &size in &[1, 10, 100, 1000] {
+            let peers = (0..size)
+                .map(|id| peer(id, &[SenderKind::Audio, SenderKind::Video]))
+                .collect();
+            let (room, server, _timer) = joined_room(config, peers);
+
+            let started = std::time::Instant::now();
+            for _ in 0..ROUNDS {
+                let op = spawn(room.inner_mute(media, config.mute_timeout));
+                server.send(muted(media));
+                run_until_stalled();
+                assert!(matches!(op.peek(), Some(Ok(_))));
+
+                let op = spawn(room.inner_unmute(media, config.unmute_timeout));
+                server.send(unmuted(media));
+                run_until_stalled();
+                assert!(matches!(op.peek(), Some(Ok(_))));
+            }
+            println!(
+                "{:>4} peers: {:?} per mute and unmute",
+                size,
+                started.elapsed() / ROUNDS,
+            );
+        }
+    }
+
+    /// Returns strategy of JSON values shaped like [`ServerMsg`]s and
+    /// [`ClientMsg`]s: known message and field names with values of any
+    /// type, so some of them are parsed successfully.
+    fn message_json(
+    ) -> impl proptest::strategy::Strategy<Value = serde_json::Value> {
+        use proptest::{collection, prelude::*};
+        use serde_json::{Map, Value};
+
+        let name = proptest::sample::select(vec![
+            "RoomMuted",
+            "RoomUnmuted",
+            "RoomHeld",
+            "RoomResumed",
+            "PeerMuted",
+            "ForceMuted",
+            "ForceMuteReleased",
+            "SenderAdded",
+            "SenderRemoved",
+            "HelloAccepted",
+            "HelloRejected",
+            "RoomJoined",
+            "MemberMuted",
+            "TrackMuted",
+            "RoomState",
+            "MuteRoom",
+            "UnmuteRoom",
+            "HoldRoom",
+            "ResumeRoom",
+            "MutePeer",
+            "Hello",
+            "JoinRoom",
+            "MuteTrack",
+            "Unknown",
+        ]);
+        let field = proptest::sample::select(vec![
+            "audio",
+            "video",
+            "display",
+            "direction",
+            "peer_id",
+            "track_id",
+            "kind",
+            "reason",
+            "role",
+            "peers",
+            "senders",
+            "muted",
+            "token",
+        ])
+        .prop_map(str::to_owned);
+        let leaf = prop_oneof![
+            Just(Value::Null),
+            any::<bool>().prop_map(Value::from),
+            (-2..4).prop_map(Value::from),
+            any::<i64>().prop_map(Value::from),
+            proptest::sample::select(vec![
+                "audio",
+                "video",
+                "display",
+                "send",
+                "recv",
+                "both",
+                "member",
+                "moderator",
+            ])
+            .prop_map(Value::from),
+            any::<String>().prop_map(Value::from),
+        ];
+        let value = leaf.prop_recursive(3, 32, 4, move |inner| {
+            let object =
+                collection::btree_map(field.clone(), inner.clone(), 0..6)
+                    .prop_map(|fields| {
+                        Value::Object(fields.into_iter().collect())
+                    });
+            prop_oneof![
+                collection::vec(inner, 0..4).prop_map(Value::from),
+                object,
+            ]
+        });
+        let room_id = prop_oneof![
+            Just(Some("default".to_owned())),
+            proptest::option::of(any::<String>()),
+        ];
+        let data = proptest::option::of(value);
+        (room_id, any::<u64>(), name, data).prop_map(
+            |(room_id, seq, name, data)| {
+                let mut msg = Map::new();
+                if let Some(room_id) = room_id {
+                    msg.insert("room_id".to_owned(), room_id.into());
+                }
+                msg.insert("seq".to_owned(), seq.into());
+                msg.insert("command".to_owned(), name.into());
+                if let Some(data) = data {
+                    msg.insert("data".to_owned(), data);
+                }
+                Value::Object(msg)
+            },
+        )
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn parsing_arbitrary_string_never_panics(msg in ".*") {
+            let _ = serde_json::from_str::<ServerMsg>(&msg);
+            let _ = serde_json::from_str::<ClientMsg>(&msg);
+        }
+
+        #[test]
+        fn parsing_truncated_message_never_panics(
+            (msg, len) in message_json()
+                .prop_map(|msg| msg.to_string())
+                .prop_flat_map(|msg| {
+                    let len = msg.len();
+                    (proptest::strategy::Just(msg), 0..=len)
+                }),
+        ) {
+            let msg = String::from_utf8_lossy(&msg.as_bytes()[..len]);
+            let _ = serde_json::from_str::<ServerMsg>(&msg);
+            let _ = serde_json::from_str::<ClientMsg>(&msg);
+        }
+
+        #[test]
+        fn handling_arbitrary_messages_never_panics(
+            msgs in proptest::collection::vec(message_json(), 1..16),
+        ) {
+            let config = RoomConfig::default();
+            let peers = vec![
+                peer(1, &[SenderKind::Audio, SenderKind::Video]),
+                peer(2, &[SenderKind::Audio]),
+            ];
+            let (room, server, timer) = joined_room(config, peers);
+            let media = MediaKinds::new(true, true);
+            let _op = spawn(room.inner_mute(media, config.mute_timeout));
+
+            for msg in &msgs {
+                let msg = msg.to_string();
+                let _ = serde_json::from_str::<ClientMsg>(&msg);
+                if let Ok(msg) = serde_json::from_str::<ServerMsg>(&msg) {
+                    room.0.borrow_mut().handle_event(msg.seq, &msg.event);
+                    server.send(msg.event);
+                    run_until_stalled();
+                }
+            }
+            timer.advance(config.mute_timeout);
+            run_until_stalled();
+        }
     }
 }
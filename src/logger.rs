@@ -0,0 +1,137 @@
+//! Logger backing the `log` macros used across the crate.
+//!
+//! Logger is global, so the [`LogLevel`] and [`LogSink`] of the latest
+//! created `RoomHandle` apply to all of them.
+
+use std::{cell::RefCell, collections::VecDeque};
+
+use log::{LevelFilter, Log, Metadata, Record};
+use wasm_bindgen::prelude::*;
+
+use crate::executor::spawn_local;
+
+/// Maximum number of the recent records kept by the [`LogSink::Buffered`].
+const BUFFER_CAPACITY: usize = 500;
+
+/// Maximum level of the logged records.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum LogLevel {
+    Off,
+    Error,
+    #[default]
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl From<LogLevel> for LevelFilter {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Off => Self::Off,
+            LogLevel::Error => Self::Error,
+            LogLevel::Warn => Self::Warn,
+            LogLevel::Info => Self::Info,
+            LogLevel::Debug => Self::Debug,
+            LogLevel::Trace => Self::Trace,
+        }
+    }
+}
+
+/// Destination of the logged records.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum LogSink {
+    /// Records are written to the browser console.
+    #[default]
+    Console,
+
+    /// Recent records are kept in memory until requested.
+    Buffered,
+
+    /// Records are passed to the JS callback set via
+    /// `RoomHandle::on_log`.
+    Callback,
+}
+
+#[derive(Default)]
+struct State {
+    sink: LogSink,
+    buffer: VecDeque<String>,
+    callback: Option<js_sys::Function>,
+}
+
+thread_local! {
+    static STATE: RefCell<State> = RefCell::default();
+}
+
+struct Logger;
+
+static LOGGER: Logger = Logger;
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line = format!("[{}] {}", record.level(), record.args());
+        STATE.with(|state| {
+            let mut state = state.borrow_mut();
+            match state.sink {
+                #[cfg(target_arch = "wasm32")]
+                LogSink::Console => {
+                    let line = JsValue::from(line);
+                    match record.level() {
+                        log::Level::Error => web_sys::console::error_1(&line),
+                        log::Level::Warn => web_sys::console::warn_1(&line),
+                        _ => web_sys::console::log_1(&line),
+                    }
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                LogSink::Console => eprintln!("{}", line),
+                LogSink::Buffered => {
+                    if state.buffer.len() == BUFFER_CAPACITY {
+                        state.buffer.pop_front();
+                    }
+                    state.buffer.push_back(line);
+                }
+                LogSink::Callback => {
+                    if let Some(callback) = state.callback.clone() {
+                        let level = JsValue::from(record.level().as_str());
+                        spawn_local(async move {
+                            let _ = callback.call2(
+                                &JsValue::NULL,
+                                &level,
+                                &line.into(),
+                            );
+                        });
+                    }
+                }
+            }
+        });
+    }
+
+    fn flush(&self) {}
+}
+
+/// Installs the logger (if not yet), applying provided level and sink.
+pub fn init(level: LogLevel, sink: LogSink) {
+    let _ = log::set_logger(&LOGGER);
+    log::set_max_level(level.into());
+    STATE.with(|state| state.borrow_mut().sink = sink);
+}
+
+/// Sets callback receiving records of the [`LogSink::Callback`].
+pub fn set_callback(callback: js_sys::Function) {
+    STATE.with(|state| state.borrow_mut().callback = Some(callback));
+}
+
+/// Returns records kept by the [`LogSink::Buffered`], draining them.
+pub fn take_buffered() -> Vec<String> {
+    STATE.with(|state| state.borrow_mut().buffer.drain(..).collect())
+}
@@ -0,0 +1,107 @@
+//! Local media acquisition via `navigator.mediaDevices.getUserMedia`.
+
+use std::fmt;
+
+use wasm_bindgen::{prelude::*, JsCast};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{DomException, MediaStream, MediaStreamConstraints};
+
+pub use web_sys::MediaStreamTrack;
+
+/// Errors which may occur while acquiring local media.
+#[derive(Debug)]
+pub enum MediaError {
+    /// User or browser denied access to the requested devices.
+    PermissionDenied(String),
+
+    /// No devices satisfying the requested constraints were found.
+    NotFound(String),
+
+    /// `navigator.mediaDevices` is unavailable (e.g. insecure context).
+    Unsupported,
+
+    /// Any other `getUserMedia` failure.
+    Other(String),
+}
+
+impl MediaError {
+    /// Returns name of this error, which is used as `name` of the JS error.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::PermissionDenied(_) => "PermissionDenied",
+            Self::NotFound(_) => "NotFound",
+            Self::Unsupported => "Unsupported",
+            Self::Other(_) => "MediaError",
+        }
+    }
+}
+
+impl fmt::Display for MediaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PermissionDenied(msg) => {
+                write!(f, "Access to media devices denied: {}", msg)
+            }
+            Self::NotFound(msg) => {
+                write!(f, "Requested media devices not found: {}", msg)
+            }
+            Self::Unsupported => {
+                write!(f, "navigator.mediaDevices is not available")
+            }
+            Self::Other(msg) => write!(f, "Failed to get user media: {}", msg),
+        }
+    }
+}
+
+impl From<JsValue> for MediaError {
+    fn from(err: JsValue) -> Self {
+        match err.dyn_into::<DomException>() {
+            Ok(err) => match err.name().as_str() {
+                "NotAllowedError" | "SecurityError" => {
+                    Self::PermissionDenied(err.message())
+                }
+                "NotFoundError" | "OverconstrainedError" => {
+                    Self::NotFound(err.message())
+                }
+                _ => Self::Other(err.message()),
+            },
+            Err(err) => Self::Other(format!("{:?}", err)),
+        }
+    }
+}
+
+impl From<MediaError> for JsValue {
+    fn from(err: MediaError) -> Self {
+        let js_err = js_sys::Error::new(&err.to_string());
+        js_err.set_name(err.name());
+        js_err.into()
+    }
+}
+
+/// Requests camera and/or microphone and returns acquired tracks.
+pub async fn get_user_media(
+    audio: bool,
+    video: bool,
+) -> Result<Vec<MediaStreamTrack>, MediaError> {
+    let media_devices = web_sys::window()
+        .ok_or(MediaError::Unsupported)?
+        .navigator()
+        .media_devices()
+        .map_err(|_| MediaError::Unsupported)?;
+
+    let constraints = MediaStreamConstraints::new();
+    constraints.set_audio(&audio.into());
+    constraints.set_video(&video.into());
+
+    let stream: MediaStream = JsFuture::from(
+        media_devices.get_user_media_with_constraints(&constraints)?,
+    )
+    .await?
+    .unchecked_into();
+
+    Ok(stream
+        .get_tracks()
+        .iter()
+        .map(JsCast::unchecked_into)
+        .collect())
+}
@@ -0,0 +1,60 @@
+//! Wrappers around the browser WebRTC API driven by the `PeerConnection`.
+
+use wasm_bindgen::JsValue;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    MediaStreamTrack, RtcRtpTransceiverDirection, RtcRtpTransceiverInit,
+};
+
+use crate::proto::SenderKind;
+
+/// Wrapper around the `RTCPeerConnection`, which is closed on drop.
+#[derive(Debug)]
+pub struct RtcPeerConnection(web_sys::RtcPeerConnection);
+
+impl RtcPeerConnection {
+    /// Creates new `RTCPeerConnection`.
+    ///
+    /// Errors if WebRTC isn't supported by the current environment.
+    pub fn new() -> Result<Self, JsValue> {
+        web_sys::RtcPeerConnection::new().map(Self)
+    }
+
+    /// Adds new send-only [`Transceiver`] for the media of the provided
+    /// kind.
+    pub fn add_transceiver(&self, kind: SenderKind) -> Transceiver {
+        let media = match kind {
+            SenderKind::Audio => "audio",
+            SenderKind::Video | SenderKind::Display => "video",
+        };
+        let init = RtcRtpTransceiverInit::new();
+        init.set_direction(RtcRtpTransceiverDirection::Sendonly);
+        Transceiver(self.0.add_transceiver_with_str_and_init(media, &init))
+    }
+}
+
+impl Drop for RtcPeerConnection {
+    fn drop(&mut self) {
+        self.0.close();
+    }
+}
+
+/// Wrapper around the `RTCRtpTransceiver`, which is stopped on drop.
+#[derive(Debug)]
+pub struct Transceiver(web_sys::RtcRtpTransceiver);
+
+impl Transceiver {
+    /// Sets `MediaStreamTrack` sent by this [`Transceiver`]. `None` stops
+    /// sending without renegotiation.
+    ///
+    /// Returned `Future` resolves once the track is actually replaced.
+    pub fn replace_track(&self, track: Option<&MediaStreamTrack>) -> JsFuture {
+        JsFuture::from(self.0.sender().replace_track(track))
+    }
+}
+
+impl Drop for Transceiver {
+    fn drop(&mut self) {
+        self.0.stop();
+    }
+}
@@ -0,0 +1,33 @@
+//! Persistence of the user's mute preference in the `localStorage`.
+
+use serde::{Deserialize, Serialize};
+
+/// Key of the `localStorage` item storing [`MutePreference`].
+const STORAGE_KEY: &str = "mute-unmute-poc.mute";
+
+/// Last mute configuration chosen by user.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub struct MutePreference {
+    pub audio: bool,
+    pub video: bool,
+}
+
+/// Returns `localStorage` of the current window (if available).
+fn storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+/// Loads previously saved [`MutePreference`] (if any).
+pub fn load() -> Option<MutePreference> {
+    let item = storage()?.get_item(STORAGE_KEY).ok()??;
+    serde_json::from_str(&item).ok()
+}
+
+/// Saves provided [`MutePreference`], ignoring unavailable `localStorage`.
+pub fn save(preference: MutePreference) {
+    if let (Some(storage), Ok(item)) =
+        (storage(), serde_json::to_string(&preference))
+    {
+        let _ = storage.set_item(STORAGE_KEY, &item);
+    }
+}
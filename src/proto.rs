@@ -1,15 +1,209 @@
 use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+/// [`Event`] numbered by server and addressed to a room.
+#[derive(Clone, Debug, Deserialize, Serialize, Hash, Eq, PartialEq)]
+pub struct ServerMsg {
+    /// ID of the room which this event is addressed to.
+    pub room_id: RoomId,
+
+    /// Sequence number of the event, increasing with each next one sent to
+    /// the same room.
+    pub seq: u64,
+
+    #[serde(flatten)]
+    pub event: Event,
+}
+
+/// [`Command`] addressed to a room.
+#[derive(Clone, Debug, Deserialize, Serialize, Hash, Eq, PartialEq)]
+pub struct ClientMsg {
+    /// ID of the room which this command is addressed to.
+    pub room_id: RoomId,
+
+    #[serde(flatten)]
+    pub command: Command,
+}
 
 #[derive(Clone, Debug, Deserialize, Serialize, Hash, Eq, PartialEq)]
 #[serde(tag = "command", content = "data")]
 pub enum Event {
-    RoomMuted { video: bool, audio: bool },
-    RoomUnmuted { video: bool, audio: bool },
+    RoomMuted {
+        video: bool,
+        audio: bool,
+        #[serde(default)]
+        display: bool,
+        #[serde(default)]
+        direction: MediaDirection,
+    },
+    RoomUnmuted {
+        video: bool,
+        audio: bool,
+        #[serde(default)]
+        display: bool,
+        #[serde(default)]
+        direction: MediaDirection,
+    },
+    RoomHeld,
+    RoomResumed,
+    PeerMuted {
+        peer_id: i32,
+        video: bool,
+        audio: bool,
+    },
+    ForceMuted {
+        video: bool,
+        audio: bool,
+    },
+    ForceMuteReleased {
+        video: bool,
+        audio: bool,
+    },
+    SenderAdded {
+        peer_id: i32,
+        track_id: String,
+        kind: SenderKind,
+    },
+    SenderRemoved {
+        peer_id: i32,
+        track_id: String,
+    },
+    HelloAccepted,
+    HelloRejected {
+        reason: String,
+    },
+    RoomJoined {
+        role: Role,
+        peers: Vec<PeerState>,
+    },
+    MemberMuted {
+        peer_id: i32,
+        audio: bool,
+        video: bool,
+    },
+    TrackMuted {
+        peer_id: i32,
+        track_id: String,
+    },
+    /// Full state of the room, sent after (re)connection.
+    RoomState {
+        peers: Vec<PeerState>,
+    },
+}
+
+/// State of a single peer in the [`Event::RoomState`].
+#[derive(Clone, Debug, Deserialize, Serialize, Hash, Eq, PartialEq)]
+pub struct PeerState {
+    pub peer_id: i32,
+    pub senders: Vec<SenderState>,
+}
+
+/// State of a single sender in the [`Event::RoomState`].
+#[derive(Clone, Debug, Deserialize, Serialize, Hash, Eq, PartialEq)]
+pub struct SenderState {
+    pub track_id: String,
+    pub kind: SenderKind,
+    pub muted: bool,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize, Hash, Eq, PartialEq)]
 #[serde(tag = "command", content = "data")]
 pub enum Command {
-    MuteRoom { video: bool, audio: bool },
-    UnmuteRoom { video: bool, audio: bool },
+    MuteRoom {
+        video: bool,
+        audio: bool,
+        #[serde(default)]
+        display: bool,
+        #[serde(default)]
+        direction: MediaDirection,
+    },
+    UnmuteRoom {
+        video: bool,
+        audio: bool,
+        #[serde(default)]
+        display: bool,
+        #[serde(default)]
+        direction: MediaDirection,
+    },
+    HoldRoom,
+    ResumeRoom,
+    MutePeer {
+        peer_id: i32,
+        video: bool,
+        audio: bool,
+    },
+    Hello {
+        token: String,
+    },
+    JoinRoom {
+        role: Role,
+    },
+    MuteTrack {
+        peer_id: i32,
+        track_id: String,
+    },
+}
+
+/// ID of the room, which messages are multiplexed by.
+#[derive(Clone, Debug, Deserialize, Serialize, Hash, Eq, PartialEq)]
+pub struct RoomId(pub String);
+
+/// Role of the user in a room.
+#[wasm_bindgen]
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Serialize, Hash, Eq, PartialEq,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// Regular participant, who can only mute and unmute own media.
+    #[default]
+    Member,
+
+    /// Participant, who can also mute other participants.
+    Moderator,
+}
+
+/// Kind of the media of a track.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, Hash, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SenderKind {
+    Video,
+    Audio,
+    Display,
+}
+
+/// Direction of the media which mute is applied to.
+#[wasm_bindgen]
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Serialize, Hash, Eq, PartialEq,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum MediaDirection {
+    /// Outgoing media only.
+    #[default]
+    Send,
+
+    /// Incoming media only.
+    Recv,
+
+    /// Both outgoing and incoming media.
+    Both,
+}
+
+impl MediaDirection {
+    /// Indicates whether outgoing media is included.
+    pub fn is_send(self) -> bool {
+        matches!(self, Self::Send | Self::Both)
+    }
+
+    /// Indicates whether incoming media is included.
+    pub fn is_recv(self) -> bool {
+        matches!(self, Self::Recv | Self::Both)
+    }
+
+    /// Indicates whether this and the provided directions have common media.
+    pub fn overlaps(self, other: MediaDirection) -> bool {
+        (self.is_send() && other.is_send())
+            || (self.is_recv() && other.is_recv())
+    }
 }
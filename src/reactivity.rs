@@ -0,0 +1,258 @@
+//! Primitives for observing changes of the values.
+
+use std::fmt;
+
+use futures::{
+    channel::{mpsc, oneshot},
+    future::LocalBoxFuture,
+    FutureExt as _, Stream,
+};
+
+/// Predicate of the [`ReactiveField::when`] subscription.
+type Assert<T> = Box<dyn Fn(&T) -> bool>;
+
+/// Sender resolving the [`ReactiveField::when`] subscription.
+type WhenSender = oneshot::Sender<Result<(), WhenError>>;
+
+/// `Future` returned by the [`ReactiveField::when`].
+pub type When = LocalBoxFuture<'static, Result<(), WhenError>>;
+
+/// Reason of the [`ReactiveField::when`] subscription never resolving.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum WhenError {
+    /// Subscription was dropped via [`ReactiveField::drop_when_subs`].
+    Cancelled,
+
+    /// [`ReactiveField`] itself was dropped.
+    Dropped,
+}
+
+/// Field whose modifications can be awaited and subscribed to.
+pub struct ReactiveField<T> {
+    data: T,
+    when_subs: Vec<(Assert<T>, WhenSender)>,
+    subs: Vec<mpsc::UnboundedSender<()>>,
+}
+
+impl<T> ReactiveField<T>
+where
+    T: PartialEq + 'static,
+{
+    pub fn new(data: T) -> Self {
+        Self {
+            data,
+            when_subs: Vec::new(),
+            subs: Vec::new(),
+        }
+    }
+
+    /// Returns reference to the current value.
+    pub fn get(&self) -> &T {
+        &self.data
+    }
+
+    /// Sets new value, notifying all subscribers if it differs from the
+    /// current one.
+    pub fn set(&mut self, data: T) {
+        if self.data == data {
+            return;
+        }
+        self.data = data;
+
+        let data = &self.data;
+        let mut when_subs = Vec::with_capacity(self.when_subs.len());
+        for (assert, tx) in self.when_subs.drain(..) {
+            if tx.is_canceled() {
+                continue;
+            }
+            if assert(data) {
+                let _ = tx.send(Ok(()));
+            } else {
+                when_subs.push((assert, tx));
+            }
+        }
+        self.when_subs = when_subs;
+        self.subs.retain(|tx| tx.unbounded_send(()).is_ok());
+    }
+
+    /// Returns `Future` which resolves when the value satisfies provided
+    /// predicate. Resolves immediately if it already does.
+    ///
+    /// Resolves with [`WhenError::Dropped`] if this field is dropped before.
+    pub fn when<F>(&mut self, assert: F) -> When
+    where
+        F: Fn(&T) -> bool + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        if assert(&self.data) {
+            let _ = tx.send(Ok(()));
+        } else {
+            self.when_subs.push((Box::new(assert), tx));
+        }
+        rx.map(|res| res.unwrap_or(Err(WhenError::Dropped)))
+            .boxed_local()
+    }
+
+    /// Drops all pending [`ReactiveField::when`] subscriptions, so their
+    /// `Future`s resolve with [`WhenError::Cancelled`].
+    pub fn drop_when_subs(&mut self) {
+        for (_, tx) in self.when_subs.drain(..) {
+            let _ = tx.send(Err(WhenError::Cancelled));
+        }
+    }
+
+    /// Returns `Future` which resolves when the value becomes equal to the
+    /// provided one.
+    pub fn when_eq(&mut self, should_be: T) -> When {
+        self.when(move |data| data == &should_be)
+    }
+
+    /// Returns `Stream` which yields on each modification of this field.
+    ///
+    /// `Stream` ends when this field is dropped.
+    pub fn on_change(&mut self) -> impl Stream<Item = ()> {
+        let (tx, rx) = mpsc::unbounded();
+        self.subs.push(tx);
+        rx
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ReactiveField<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReactiveField")
+            .field("data", &self.data)
+            .field("when_subs", &self.when_subs.len())
+            .field("subs", &self.subs.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::task::{Context, Poll};
+
+    use futures::{task::noop_waker_ref, FutureExt as _, StreamExt as _};
+
+    use super::*;
+
+    /// Polls provided [`When`] once, without waiting for it.
+    fn poll(when: &mut When) -> Poll<Result<(), WhenError>> {
+        when.as_mut()
+            .poll(&mut Context::from_waker(noop_waker_ref()))
+    }
+
+    #[test]
+    fn many_when_subs_resolve_by_their_predicates() {
+        let mut field = ReactiveField::new(0_u32);
+        let mut subs: Vec<_> = (0..2000_u32)
+            .map(|i| {
+                let threshold = i % 100 + 1;
+                let when = if i % 2 == 0 {
+                    field.when(move |data| *data >= threshold)
+                } else {
+                    field.when_eq(threshold)
+                };
+                (i % 2 == 0, threshold, Some(when))
+            })
+            .collect();
+
+        for value in 1..=100 {
+            field.set(value);
+            for (is_ge, threshold, when) in &mut subs {
+                let is_due = if *is_ge {
+                    value >= *threshold
+                } else {
+                    value == *threshold
+                };
+                match when.as_mut().map(poll) {
+                    Some(Poll::Ready(res)) => {
+                        assert!(is_due, "{} resolved at {}", threshold, value);
+                        assert_eq!(res, Ok(()));
+                        *when = None;
+                    }
+                    Some(Poll::Pending) => {
+                        assert!(!is_due, "{} pending at {}", threshold, value);
+                    }
+                    None => (),
+                }
+            }
+        }
+
+        assert!(subs.iter().all(|(_, _, when)| when.is_none()));
+        assert!(field.when_subs.is_empty());
+    }
+
+    #[test]
+    fn dropped_when_subs_are_not_leaked() {
+        let mut field = ReactiveField::new(0);
+        let mut subs: Vec<_> = (1..=1000).map(|i| field.when_eq(i)).collect();
+        subs.truncate(300);
+
+        field.set(-1);
+
+        assert_eq!(field.when_subs.len(), 300);
+        drop(subs);
+        field.set(-2);
+        assert!(field.when_subs.is_empty());
+    }
+
+    #[test]
+    fn drop_when_subs_cancels_polled_subs() {
+        let mut field = ReactiveField::new(0);
+        let mut subs: Vec<_> = (1..=1000).map(|i| field.when_eq(i)).collect();
+        for when in &mut subs {
+            assert_eq!(poll(when), Poll::Pending);
+        }
+
+        field.drop_when_subs();
+        field.set(1);
+
+        for when in &mut subs {
+            assert_eq!(poll(when), Poll::Ready(Err(WhenError::Cancelled)));
+        }
+        assert!(field.when_subs.is_empty());
+        assert_eq!(field.when_eq(2).now_or_never(), None);
+        field.set(2);
+        assert!(field.when_subs.is_empty());
+    }
+
+    #[test]
+    fn pending_when_subs_resolve_once_field_is_dropped() {
+        let mut field = ReactiveField::new(0);
+        let mut subs: Vec<_> = (1..=100).map(|i| field.when_eq(i)).collect();
+
+        drop(field);
+
+        for when in &mut subs {
+            assert_eq!(poll(when), Poll::Ready(Err(WhenError::Dropped)));
+        }
+    }
+
+    #[test]
+    fn repeated_on_change_yields_on_each_modification() {
+        let mut field = ReactiveField::new(0);
+        let mut subs: Vec<_> =
+            (0..300).map(|_| Some((field.on_change(), 0))).collect();
+
+        for value in 1..=50 {
+            field.set(value);
+            // Setting the same value isn't a modification.
+            field.set(value);
+            if value == 25 {
+                subs.iter_mut().step_by(3).for_each(|sub| *sub = None);
+            }
+            for (changes, count) in subs.iter_mut().flatten() {
+                while let Some(Some(())) = changes.next().now_or_never() {
+                    *count += 1;
+                }
+            }
+        }
+
+        assert_eq!(field.subs.len(), 200);
+        assert!(subs.iter().flatten().all(|(_, count)| *count == 50));
+        drop(field);
+        for (changes, _) in subs.iter_mut().flatten() {
+            assert_eq!(changes.next().now_or_never(), Some(None));
+        }
+    }
+}
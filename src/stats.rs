@@ -0,0 +1,217 @@
+//! Statistics of the mute transitions of the peers.
+
+use std::collections::{HashMap, VecDeque};
+
+use serde::Serialize;
+
+use crate::{executor, OperationKey, OperationKind, PeerId};
+
+/// Statistics of a single peer.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct PeerStats {
+    /// ID of the peer these statistics belong to.
+    peer_id: i32,
+
+    /// Number of the confirmed mutes.
+    mutes: u32,
+
+    /// Number of the confirmed unmutes.
+    unmutes: u32,
+
+    /// Number of the transitions which weren't confirmed in time.
+    timeouts: u32,
+
+    /// Time between the last sent command and its confirmation.
+    last_latency_ms: Option<f64>,
+
+    /// Average time between a sent command and its confirmation.
+    avg_latency_ms: Option<f64>,
+
+    #[serde(skip)]
+    total_latency_ms: f64,
+
+    #[serde(skip)]
+    confirmed: u32,
+}
+
+impl PeerStats {
+    fn new(peer_id: PeerId) -> Self {
+        Self {
+            peer_id: peer_id.0,
+            ..Self::default()
+        }
+    }
+}
+
+/// Upper bounds (in milliseconds) of the [`LatencyStats`] histogram
+/// buckets. The last bucket is unbounded.
+const HISTOGRAM_BOUNDS: [f64; 6] =
+    [100.0, 250.0, 500.0, 1000.0, 2000.0, 4000.0];
+
+/// Number of the latest samples the [`LatencyStats`] rolling average is
+/// calculated over.
+const ROLLING_WINDOW: usize = 50;
+
+/// Latencies of the confirmations of the operations of the same kind.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct LatencyStats {
+    /// Number of the confirmed operations.
+    count: u32,
+
+    /// Minimal latency.
+    min_ms: Option<f64>,
+
+    /// Maximal latency.
+    max_ms: Option<f64>,
+
+    /// Average latency of the last [`ROLLING_WINDOW`] operations.
+    rolling_avg_ms: Option<f64>,
+
+    /// Number of the operations in each of the [`HISTOGRAM_BOUNDS`] buckets.
+    histogram: [u32; HISTOGRAM_BOUNDS.len() + 1],
+
+    #[serde(skip)]
+    window: VecDeque<f64>,
+}
+
+impl LatencyStats {
+    fn record(&mut self, latency: f64) {
+        self.count += 1;
+        self.min_ms = Some(self.min_ms.map_or(latency, |min| min.min(latency)));
+        self.max_ms = Some(self.max_ms.map_or(latency, |max| max.max(latency)));
+
+        if self.window.len() == ROLLING_WINDOW {
+            self.window.pop_front();
+        }
+        self.window.push_back(latency);
+        let sum: f64 = self.window.iter().sum();
+        self.rolling_avg_ms = Some(sum / self.window.len() as f64);
+
+        let bucket = HISTOGRAM_BOUNDS
+            .iter()
+            .position(|bound| latency <= *bound)
+            .unwrap_or(HISTOGRAM_BOUNDS.len());
+        self.histogram[bucket] += 1;
+    }
+}
+
+/// [`LatencyStats`] of the mute and unmute operations.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct OperationLatencies {
+    mute: LatencyStats,
+    unmute: LatencyStats,
+}
+
+/// Collector of the [`PeerStats`] of all peers of the [`Room`].
+///
+/// [`Room`]: crate::Room
+#[derive(Debug, Default)]
+pub struct Stats {
+    peers: HashMap<PeerId, PeerStats>,
+
+    /// [`PeerStats`] gathered by the last [`Stats::collect`].
+    collected: Vec<PeerStats>,
+
+    /// Timestamps of the sent commands which aren't confirmed yet.
+    started_at: HashMap<OperationKey, f64>,
+
+    latencies: OperationLatencies,
+}
+
+impl Stats {
+    /// Remembers the time when command of the operation with the provided
+    /// [`OperationKey`] was sent.
+    pub fn command_sent(&mut self, key: OperationKey) {
+        self.started_at.entry(key).or_insert_with(executor::now);
+    }
+
+    /// Records confirmed transition of the provided peer.
+    ///
+    /// Should be followed by the [`Stats::confirmed`] once all peers are
+    /// recorded.
+    pub fn record_transition(&mut self, peer_id: PeerId, key: OperationKey) {
+        let latency = self
+            .started_at
+            .get(&key)
+            .map(|started_at| executor::now() - started_at);
+        let stats = self
+            .peers
+            .entry(peer_id)
+            .or_insert_with(|| PeerStats::new(peer_id));
+        match key.kind {
+            OperationKind::Mute => stats.mutes += 1,
+            OperationKind::Unmute => stats.unmutes += 1,
+        }
+        if let Some(latency) = latency {
+            stats.confirmed += 1;
+            stats.total_latency_ms += latency;
+            stats.last_latency_ms = Some(latency);
+            stats.avg_latency_ms =
+                Some(stats.total_latency_ms / f64::from(stats.confirmed));
+        }
+    }
+
+    /// Marks command of the operation with the provided [`OperationKey`] as
+    /// confirmed.
+    pub fn confirmed(&mut self, key: OperationKey) {
+        if let Some(started_at) = self.started_at.remove(&key) {
+            let latency = executor::now() - started_at;
+            match key.kind {
+                OperationKind::Mute => self.latencies.mute.record(latency),
+                OperationKind::Unmute => self.latencies.unmute.record(latency),
+            }
+        }
+    }
+
+    /// Returns [`OperationLatencies`] of all the confirmed operations.
+    pub fn latencies(&self) -> &OperationLatencies {
+        &self.latencies
+    }
+
+    /// Records that command of the operation with the provided
+    /// [`OperationKey`] wasn't confirmed in time for the provided peers.
+    pub fn timed_out<I>(&mut self, peers: I, key: OperationKey)
+    where
+        I: IntoIterator<Item = PeerId>,
+    {
+        self.started_at.remove(&key);
+        for peer_id in peers {
+            self.peers
+                .entry(peer_id)
+                .or_insert_with(|| PeerStats::new(peer_id))
+                .timeouts += 1;
+        }
+    }
+
+    /// Gathers statistics of the provided peers, replacing the previously
+    /// gathered ones.
+    pub fn collect<I>(&mut self, peers: I)
+    where
+        I: IntoIterator<Item = PeerId>,
+    {
+        self.collected = self.get(peers);
+    }
+
+    /// Returns [`PeerStats`] gathered by the last [`Stats::collect`].
+    pub fn collected(&self) -> &[PeerStats] {
+        &self.collected
+    }
+
+    /// Returns statistics of the provided peers.
+    fn get<I>(&self, peers: I) -> Vec<PeerStats>
+    where
+        I: IntoIterator<Item = PeerId>,
+    {
+        let mut stats: Vec<_> = peers
+            .into_iter()
+            .map(|peer_id| {
+                self.peers
+                    .get(&peer_id)
+                    .cloned()
+                    .unwrap_or_else(|| PeerStats::new(peer_id))
+            })
+            .collect();
+        stats.sort_by_key(|stats| stats.peer_id);
+        stats
+    }
+}
@@ -0,0 +1,110 @@
+//! Helpers for asserting outcomes of the `RoomHandle` promises in tests.
+
+use js_sys::Promise;
+use wasm_bindgen::convert::TryFromJsValue as _;
+use wasm_bindgen_futures::JsFuture;
+
+use crate::{RoomError, RoomErrorKind};
+
+/// Awaits provided `Promise` and returns the [`RoomError`] it rejected with.
+///
+/// # Panics
+///
+/// If the `Promise` resolves, or rejects with something other than
+/// [`RoomError`].
+pub async fn rejection(promise: Promise) -> RoomError {
+    match JsFuture::from(promise).await {
+        Ok(value) => panic!("Promise resolved with {:?}", value),
+        Err(value) => {
+            RoomError::try_from_js_value(value).unwrap_or_else(|value| {
+                panic!("Promise rejected with non-RoomError {:?}", value)
+            })
+        }
+    }
+}
+
+/// Awaits provided `Promise` and asserts that it rejects with the
+/// [`RoomError`] of the provided [`RoomErrorKind`], returning this error for
+/// further checks.
+///
+/// # Panics
+///
+/// If the `Promise` resolves, or rejects with anything else.
+pub async fn assert_rejects_with(
+    promise: Promise,
+    kind: RoomErrorKind,
+) -> RoomError {
+    let err = rejection(promise).await;
+    assert_eq!(err.kind(), kind, "Unexpected rejection: {:?}", err);
+    err
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use wasm_bindgen::{closure::Closure, JsCast as _, JsValue};
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::{MediaKinds, RoomConfig, RoomHandle};
+
+    use super::*;
+
+    #[wasm_bindgen_test]
+    async fn rejection_returns_room_error() {
+        let err = RoomError::new(
+            RoomErrorKind::Timeout,
+            "Mute confirmation timed out",
+            MediaKinds::new(true, false),
+        )
+        .with_peers(vec![1]);
+        let promise = Promise::reject(&err.into());
+
+        let err = rejection(promise).await;
+
+        assert_eq!(err.kind(), RoomErrorKind::Timeout);
+        assert_eq!(err.message(), "Mute confirmation timed out");
+        assert_eq!(err.peer_ids(), vec![1]);
+    }
+
+    #[wasm_bindgen_test]
+    async fn assert_rejects_with_checks_kind_of_room_promise() {
+        let timer = Rc::new(crate::ManualTimer::new());
+        let room = RoomHandle::with_timer(RoomConfig::default(), timer);
+        room.close();
+
+        let promise = room.mute(true, false, None, None);
+        let err = assert_rejects_with(promise, RoomErrorKind::Closed).await;
+
+        assert!(err.audio());
+        assert!(!err.video());
+    }
+
+    #[wasm_bindgen_test]
+    async fn error_is_dispatched_for_operation_rejected_before_start() {
+        let timer = Rc::new(crate::ManualTimer::new());
+        let room = RoomHandle::with_timer(RoomConfig::default(), timer);
+        let errors = Rc::new(Cell::new(0));
+        let listener = {
+            let errors = Rc::clone(&errors);
+            Closure::<dyn Fn(JsValue)>::new(move |_| {
+                errors.set(errors.get() + 1);
+            })
+        };
+        room.add_event_listener(
+            "error".to_owned(),
+            listener
+                .as_ref()
+                .unchecked_ref::<js_sys::Function>()
+                .clone(),
+        );
+
+        let promise = room.mute_all_except(vec![1]);
+        assert_rejects_with(promise, RoomErrorKind::PermissionDenied).await;
+        JsFuture::from(Promise::resolve(&JsValue::NULL))
+            .await
+            .unwrap();
+
+        assert_eq!(errors.get(), 1);
+    }
+}
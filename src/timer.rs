@@ -0,0 +1,82 @@
+//! Timers driving timeouts and delays of the `RoomHandle`.
+
+#[cfg(feature = "test-utils")]
+use std::{cell::RefCell, rc::Rc};
+
+#[cfg(feature = "test-utils")]
+use futures::channel::oneshot;
+use futures::{future::LocalBoxFuture, FutureExt as _};
+
+use crate::resolve_after;
+
+/// Source of the delays used for timeouts of the operations.
+pub trait Timer {
+    /// Returns `Future` which resolves after provided number of
+    /// milliseconds.
+    fn delay(&self, delay_ms: i32) -> LocalBoxFuture<'static, ()>;
+}
+
+/// [`Timer`] backed by the `window.setTimeout`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BrowserTimer;
+
+impl Timer for BrowserTimer {
+    fn delay(&self, delay_ms: i32) -> LocalBoxFuture<'static, ()> {
+        resolve_after(delay_ms).map(drop).boxed_local()
+    }
+}
+
+/// [`Timer`] which time is advanced manually via [`ManualTimer::advance`],
+/// so timeouts can be triggered without real waiting.
+#[cfg(feature = "test-utils")]
+#[derive(Clone, Debug, Default)]
+pub struct ManualTimer(Rc<RefCell<ManualTimerInner>>);
+
+#[cfg(feature = "test-utils")]
+#[derive(Debug, Default)]
+struct ManualTimerInner {
+    /// Milliseconds passed since creation of the [`ManualTimer`].
+    now: i64,
+
+    /// Pending delays with their deadlines.
+    pending: Vec<(i64, oneshot::Sender<()>)>,
+}
+
+#[cfg(feature = "test-utils")]
+impl ManualTimer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Advances time of this [`ManualTimer`] by provided number of
+    /// milliseconds, resolving all the delays which are due.
+    pub fn advance(&self, ms: i32) {
+        let mut inner = self.0.borrow_mut();
+        inner.now += i64::from(ms);
+        let now = inner.now;
+        let (due, pending) = inner
+            .pending
+            .drain(..)
+            .partition(|(deadline, _)| *deadline <= now);
+        inner.pending = pending;
+        drop(inner);
+        for (_, tx) in due {
+            let _ = tx.send(());
+        }
+    }
+}
+
+#[cfg(feature = "test-utils")]
+impl Timer for ManualTimer {
+    fn delay(&self, delay_ms: i32) -> LocalBoxFuture<'static, ()> {
+        let (tx, rx) = oneshot::channel();
+        let mut inner = self.0.borrow_mut();
+        let deadline = inner.now + i64::from(delay_ms);
+        if delay_ms <= 0 {
+            let _ = tx.send(());
+        } else {
+            inner.pending.push((deadline, tx));
+        }
+        rx.map(drop).boxed_local()
+    }
+}
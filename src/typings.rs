@@ -0,0 +1,139 @@
+//! TypeScript definitions of the objects passed between JS and the
+//! `RoomHandle`, referenced by its methods instead of `any`.
+
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen(typescript_custom_section)]
+const TYPES: &'static str = r#"
+/** Kind of the media of a track. */
+export type SenderKind = "video" | "audio" | "display";
+
+/** Direction of the media in the objects passed to JS. */
+export type MediaDirectionName = "send" | "recv" | "both";
+
+/** Aggregated mute state of a single kind of media. */
+export type AggregateMuteState =
+    "unmuted" | "muted" | "partially_muted" | "in_progress";
+
+/** State of the connection with server. */
+export type ConnectionStateName =
+    "new" | "connecting" | "connected" | "closed";
+
+/** Name of the event passed to the `RoomHandle.add_event_listener()`. */
+export type RoomEventName =
+    "mutestatechange" | "connectionstatechange" | "error";
+
+/** Outgoing or incoming track affected by an operation. */
+export interface TrackOutcome {
+    peer_id: number;
+    kind: SenderKind;
+    direction: MediaDirectionName;
+}
+
+/**
+ * Result of the mute or unmute operation. Promises reject with `RoomError`
+ * instead, if the operation fails.
+ */
+export interface OperationReport {
+    confirmed: TrackOutcome[];
+    failed: TrackOutcome[];
+}
+
+/** Result of the `RoomHandle.mute_all_except()`. */
+export interface PeersMuteReport {
+    muted: number[];
+    failed: number[];
+}
+
+/** Aggregated mute state of the room. */
+export interface RoomMuteState {
+    audio: AggregateMuteState;
+    video: AggregateMuteState;
+    display: AggregateMuteState;
+}
+
+/** State of the room passed to the `RoomHandle.subscribe_state()`. */
+export interface RoomStateSnapshot {
+    connection: ConnectionStateName;
+    mute: RoomMuteState;
+    peer_count: number;
+}
+
+/** Mute statistics of a single peer. */
+export interface PeerStats {
+    peer_id: number;
+    mutes: number;
+    unmutes: number;
+    timeouts: number;
+    last_latency_ms?: number;
+    avg_latency_ms?: number;
+}
+
+/** Latencies of the confirmations of a single kind of operations. */
+export interface LatencyStats {
+    count: number;
+    min_ms?: number;
+    max_ms?: number;
+    rolling_avg_ms?: number;
+    histogram: number[];
+}
+
+/** Latencies returned by the `RoomHandle.mute_latency_stats()`. */
+export interface OperationLatencies {
+    mute: LatencyStats;
+    unmute: LatencyStats;
+}
+
+/** Message exchanged with server. */
+export interface ProtocolMessage {
+    command: string;
+    data?: any;
+}
+
+/** Entry returned by the `RoomHandle.event_log()`. */
+export type LogEntry =
+    { timestamp: number, event: ProtocolMessage } |
+    { timestamp: number, command: ProtocolMessage };
+
+/** Description of an outgoing track. */
+export interface TrackInfo {
+    track_id: string;
+    kind: SenderKind;
+    muted: boolean;
+    busy: boolean;
+    peer_id: number;
+}
+
+/** Peer of the room along with its outgoing tracks. */
+export interface PeerInfo {
+    peer_id: number;
+    tracks: TrackInfo[];
+}
+
+/** State of the room returned by the `RoomHandle.to_json()`. */
+export interface RoomInfo {
+    connection: ConnectionStateName;
+    role: "member" | "moderator";
+    mute: RoomMuteState;
+    peers: PeerInfo[];
+}
+
+/** Mute state of a remote participant. */
+export interface RemoteMuteState {
+    peer_id: number;
+    audio: boolean;
+    video: boolean;
+}
+
+/** Media muted by moderator. */
+export interface ForceMuted {
+    audio: boolean;
+    video: boolean;
+}
+
+/** Notification of the `RoomHandle.replace_track()`. */
+export interface TrackReplaced {
+    kind: SenderKind;
+    muted: boolean;
+}
+"#;
@@ -0,0 +1,45 @@
+//! Tracking of the page visibility via the Page Visibility API.
+
+use wasm_bindgen::prelude::*;
+use web_sys::Document;
+
+/// Listener of the `document`'s `visibilitychange` event, which is removed
+/// on drop.
+pub struct VisibilityListener {
+    document: Document,
+    closure: Closure<dyn FnMut()>,
+}
+
+impl VisibilityListener {
+    /// Subscribes provided callback to the page visibility changes. Callback
+    /// is called with `true` when the page becomes hidden and with `false`
+    /// when it becomes visible again.
+    ///
+    /// Returns `None` if there is no `document` (e.g. in a worker).
+    pub fn new<F>(mut on_change: F) -> Option<Self>
+    where
+        F: FnMut(bool) + 'static,
+    {
+        let document = web_sys::window()?.document()?;
+        let doc = document.clone();
+        let closure = Closure::wrap(
+            Box::new(move || on_change(doc.hidden())) as Box<dyn FnMut()>
+        );
+        document
+            .add_event_listener_with_callback(
+                "visibilitychange",
+                closure.as_ref().unchecked_ref(),
+            )
+            .ok()?;
+        Some(Self { document, closure })
+    }
+}
+
+impl Drop for VisibilityListener {
+    fn drop(&mut self) {
+        let _ = self.document.remove_event_listener_with_callback(
+            "visibilitychange",
+            self.closure.as_ref().unchecked_ref(),
+        );
+    }
+}
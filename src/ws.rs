@@ -1,41 +1,462 @@
-use std::rc::Rc;
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    rc::{Rc, Weak},
+};
 
-use crate::proto::{Command, Event};
-use wasm_bindgen_futures::spawn_local;
+#[cfg(feature = "test-utils")]
+use futures::channel::mpsc;
+use futures::{future::LocalBoxFuture, FutureExt as _};
 
-use crate::resolve_after;
+use crate::proto::{
+    ClientMsg, Command, Event, PeerState, RoomId, SenderKind, SenderState,
+    ServerMsg,
+};
 
+use crate::{
+    executor::spawn_local,
+    timer::{BrowserTimer, Timer},
+};
+
+/// Connection with server shared by all the rooms.
 pub struct WebSocket {
-    on_message: Option<Rc<dyn Fn(Event)>>,
+    on_message: HashMap<RoomId, Rc<dyn Fn(ServerMsg)>>,
+
+    /// Sequence number of the next event sent to each room.
+    next_seq: RefCell<HashMap<RoomId, u64>>,
+
+    /// [`Timer`] driving delays of the loopback server.
+    timer: Rc<dyn Timer>,
+
+    /// Receiver of the sent commands instead of the loopback server, if
+    /// this [`WebSocket`] is driven by a [`FakeServer`].
+    #[cfg(feature = "test-utils")]
+    fake: Option<mpsc::UnboundedSender<ClientMsg>>,
+
+    /// Failures injected via the [`Faults`] of each room.
+    #[cfg(feature = "test-utils")]
+    faults: HashMap<RoomId, Rc<FaultState>>,
 }
 
 impl WebSocket {
     pub fn new() -> Self {
-        Self { on_message: None }
+        Self::with_timer(Rc::new(BrowserTimer))
+    }
+
+    /// Creates new [`WebSocket`], which delays are driven by the provided
+    /// [`Timer`].
+    pub fn with_timer(timer: Rc<dyn Timer>) -> Self {
+        Self {
+            on_message: HashMap::new(),
+            next_seq: RefCell::default(),
+            timer,
+            #[cfg(feature = "test-utils")]
+            fake: None,
+            #[cfg(feature = "test-utils")]
+            faults: HashMap::new(),
+        }
+    }
+
+    /// Connects to the server with the provided URL.
+    ///
+    /// Loopback implementation only validates the URL.
+    pub fn connect(
+        &mut self,
+        url: &str,
+    ) -> LocalBoxFuture<'static, Result<(), String>> {
+        let is_valid = url.starts_with("ws://") || url.starts_with("wss://");
+        let url = url.to_owned();
+        let delay = self.timer.delay(100);
+        async move {
+            delay.await;
+            if is_valid {
+                Ok(())
+            } else {
+                Err(format!("Invalid server URL: {}", url))
+            }
+        }
+        .boxed_local()
     }
 
-    pub fn send(&self, cmd: Command) {
-        web_sys::console::log_1(&"Received command.".into());
-        let event_to_send = match cmd {
-            Command::MuteRoom { audio, video } => {
-                Event::RoomMuted { audio, video }
+    pub fn send(&self, msg: ClientMsg) {
+        log::debug!("Received command: {:?}", msg);
+        #[cfg(feature = "test-utils")]
+        if self
+            .faults
+            .get(&msg.room_id)
+            .is_some_and(|faults| faults.drops_next_command())
+        {
+            log::debug!("Dropped command: {:?}", msg);
+            return;
+        }
+        #[cfg(feature = "test-utils")]
+        if let Some(fake) = &self.fake {
+            let _ = fake.unbounded_send(msg);
+            return;
+        }
+        let ClientMsg { room_id, command } = msg;
+        let event_to_send = match command {
+            Command::MuteRoom {
+                audio,
+                video,
+                display,
+                direction,
+            } => Event::RoomMuted {
+                audio,
+                video,
+                display,
+                direction,
+            },
+            Command::UnmuteRoom {
+                audio,
+                video,
+                display,
+                direction,
+            } => Event::RoomUnmuted {
+                audio,
+                video,
+                display,
+                direction,
+            },
+            Command::HoldRoom => Event::RoomHeld,
+            Command::ResumeRoom => Event::RoomResumed,
+            Command::Hello { token } => {
+                if token.is_empty() {
+                    Event::HelloRejected {
+                        reason: "Empty token".to_owned(),
+                    }
+                } else {
+                    Event::HelloAccepted
+                }
             }
-            Command::UnmuteRoom { audio, video } => {
-                Event::RoomUnmuted { audio, video }
+            Command::JoinRoom { role } => Event::RoomJoined {
+                role,
+                peers: vec![PeerState {
+                    peer_id: 100,
+                    senders: vec![
+                        SenderState {
+                            track_id: "video".to_owned(),
+                            kind: SenderKind::Video,
+                            muted: false,
+                        },
+                        SenderState {
+                            track_id: "audio".to_owned(),
+                            kind: SenderKind::Audio,
+                            muted: false,
+                        },
+                    ],
+                }],
+            },
+            Command::MutePeer {
+                peer_id,
+                audio,
+                video,
+            } => Event::PeerMuted {
+                peer_id,
+                audio,
+                video,
+            },
+            Command::MuteTrack { peer_id, track_id } => {
+                Event::TrackMuted { peer_id, track_id }
             }
         };
 
-        let on_message = self.on_message.clone();
+        let msg = self.number(room_id, event_to_send);
+        let copies = self.copies(&msg.room_id);
+        let on_message = self.on_message.get(&msg.room_id).map(Rc::downgrade);
+        #[cfg(feature = "test-utils")]
+        let connection = self
+            .faults
+            .get(&msg.room_id)
+            .map(|faults| (Rc::clone(faults), faults.reconnects.get()));
+        let delay = self.timer.delay(3000);
         spawn_local(async move {
-            resolve_after(3000).await;
-            on_message.as_ref().map(move |f| (f)(event_to_send));
+            delay.await;
+            #[cfg(feature = "test-utils")]
+            if let Some((faults, reconnects)) = connection {
+                if !faults.delivers(reconnects) {
+                    return;
+                }
+            }
+            if let Some(f) = on_message.as_ref().and_then(Weak::upgrade) {
+                for _ in 0..copies {
+                    (f)(msg.clone());
+                }
+            }
+        });
+    }
+
+    /// Assigns the next sequence number to the provided [`Event`] addressed
+    /// to the provided room.
+    fn number(&self, room_id: RoomId, event: Event) -> ServerMsg {
+        let mut next_seq = self.next_seq.borrow_mut();
+        let next = next_seq.entry(room_id.clone()).or_default();
+        let seq = *next;
+        *next += 1;
+        ServerMsg {
+            room_id,
+            seq,
+            event,
+        }
+    }
+
+    /// Returns how many times the next event of the provided room should be
+    /// delivered.
+    #[cfg_attr(not(feature = "test-utils"), allow(unused_variables))]
+    fn copies(&self, room_id: &RoomId) -> usize {
+        #[cfg(feature = "test-utils")]
+        if self
+            .faults
+            .get(room_id)
+            .is_some_and(|faults| faults.duplicate_next.replace(false))
+        {
+            return 2;
+        }
+        1
+    }
+
+    pub fn on_message<F>(&mut self, room_id: RoomId, on_message: F)
+    where
+        F: Fn(ServerMsg) + 'static,
+    {
+        #[cfg(feature = "test-utils")]
+        self.faults.entry(room_id.clone()).or_default();
+        self.on_message.insert(room_id, Rc::new(on_message));
+    }
+
+    /// Drops `on_message` callback of the provided room, so no more events
+    /// will be delivered to it.
+    pub fn close(&mut self, room_id: &RoomId) {
+        self.on_message.remove(room_id);
+    }
+
+    /// Indicates whether events of the room with the provided ID are
+    /// delivered via this [`WebSocket`].
+    pub fn has_room(&self, room_id: &RoomId) -> bool {
+        self.on_message.contains_key(room_id)
+    }
+}
+
+/// Handle to the shared [`WebSocket`] scoped to a single room.
+pub struct RoomClient {
+    room_id: RoomId,
+    ws: Rc<RefCell<WebSocket>>,
+
+    /// Indicates whether this [`RoomClient`] is closed, so another room with
+    /// the same ID may be using the [`WebSocket`] already.
+    is_closed: bool,
+}
+
+impl RoomClient {
+    pub fn new(room_id: RoomId, ws: Rc<RefCell<WebSocket>>) -> Self {
+        Self {
+            room_id,
+            ws,
+            is_closed: false,
+        }
+    }
+
+    pub fn connect(
+        &self,
+        url: &str,
+    ) -> LocalBoxFuture<'static, Result<(), String>> {
+        self.ws.borrow_mut().connect(url)
+    }
+
+    pub fn send(&self, command: Command) {
+        self.ws.borrow().send(ClientMsg {
+            room_id: self.room_id.clone(),
+            command,
         });
     }
 
     pub fn on_message<F>(&mut self, on_message: F)
     where
-        F: Fn(Event) + 'static,
+        F: Fn(ServerMsg) + 'static,
     {
-        self.on_message = Some(Rc::new(on_message));
+        self.ws
+            .borrow_mut()
+            .on_message(self.room_id.clone(), on_message);
+    }
+
+    /// Stops delivering events of this room.
+    pub fn close(&mut self) {
+        if !self.is_closed {
+            self.is_closed = true;
+            self.ws.borrow_mut().close(&self.room_id);
+        }
+    }
+
+    /// Returns [`Faults`] injecting failures into the connection of this
+    /// room.
+    #[cfg(feature = "test-utils")]
+    pub fn faults(&self) -> Faults {
+        Faults::new(self.room_id.clone(), &self.ws)
+    }
+}
+
+impl Drop for RoomClient {
+    /// Drops `on_message` callback of this room, so the task handling its
+    /// events finishes once the room is gone.
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+/// Fake of the server driven by the test: commands sent by the room are
+/// collected instead of being answered, and events are delivered only when
+/// pushed via [`FakeServer::send`].
+#[cfg(feature = "test-utils")]
+pub struct FakeServer {
+    room_id: RoomId,
+    ws: Rc<RefCell<WebSocket>>,
+    sent: RefCell<mpsc::UnboundedReceiver<ClientMsg>>,
+}
+
+#[cfg(feature = "test-utils")]
+impl FakeServer {
+    /// Creates new [`FakeServer`] along with the [`RoomClient`] connected to
+    /// it, which delays are driven by the provided [`Timer`].
+    pub(crate) fn new(timer: Rc<dyn Timer>) -> (Self, RoomClient) {
+        let (tx, rx) = mpsc::unbounded();
+        let mut ws = WebSocket::with_timer(timer);
+        ws.fake = Some(tx);
+        let ws = Rc::new(RefCell::new(ws));
+        let room_id = RoomId("default".to_owned());
+        let client = RoomClient::new(room_id.clone(), Rc::clone(&ws));
+        let server = Self {
+            room_id,
+            ws,
+            sent: RefCell::new(rx),
+        };
+        (server, client)
+    }
+
+    /// Delivers provided [`Event`] to the room right away, unless its
+    /// connection is closed via [`Faults::close`].
+    pub fn send(&self, event: Event) {
+        let ws = self.ws.borrow();
+        if ws
+            .faults
+            .get(&self.room_id)
+            .is_some_and(|faults| faults.closed.get())
+        {
+            log::debug!("Dropped event: {:?}", event);
+            return;
+        }
+        let msg = ws.number(self.room_id.clone(), event);
+        let copies = ws.copies(&msg.room_id);
+        if let Some(f) = ws.on_message.get(&msg.room_id).map(Rc::clone) {
+            drop(ws);
+            for _ in 0..copies {
+                (f)(msg.clone());
+            }
+        }
+    }
+
+    /// Returns [`Faults`] injecting failures into the connection with this
+    /// [`FakeServer`].
+    pub fn faults(&self) -> Faults {
+        Faults::new(self.room_id.clone(), &self.ws)
+    }
+
+    /// Returns [`Command`]s sent by the room since the previous call.
+    pub fn take_sent(&self) -> Vec<Command> {
+        let mut sent = self.sent.borrow_mut();
+        let mut commands = Vec::new();
+        while let Ok(msg) = sent.try_recv() {
+            if msg.room_id == self.room_id {
+                commands.push(msg.command);
+            }
+        }
+        commands
+    }
+}
+
+/// Failures injected into the connection of a single room.
+#[cfg(feature = "test-utils")]
+#[derive(Debug, Default)]
+struct FaultState {
+    /// Number of the commands sent so far.
+    sent: Cell<usize>,
+
+    /// Numbers of the sent commands which should be dropped.
+    drop_at: RefCell<Vec<usize>>,
+
+    /// Indicates whether the next event should be delivered twice.
+    duplicate_next: Cell<bool>,
+
+    /// Indicates whether the connection is closed, so all the commands and
+    /// events are dropped.
+    closed: Cell<bool>,
+
+    /// Number of the reconnections, so events sent before the last one are
+    /// lost.
+    reconnects: Cell<u64>,
+}
+
+#[cfg(feature = "test-utils")]
+impl FaultState {
+    /// Counts the command being sent and indicates whether it should be
+    /// dropped.
+    fn drops_next_command(&self) -> bool {
+        let n = self.sent.get();
+        self.sent.set(n + 1);
+        self.closed.get() || self.drop_at.borrow().contains(&n)
+    }
+
+    /// Indicates whether the event sent after the provided number of the
+    /// reconnections should be delivered.
+    fn delivers(&self, reconnects: u64) -> bool {
+        !self.closed.get() && self.reconnects.get() == reconnects
+    }
+}
+
+/// Handle injecting failures into the connection of a room, driven either
+/// by the loopback server or by the [`FakeServer`].
+///
+/// Failures are scoped to the room, so other rooms sharing the same
+/// [`WebSocket`] are not affected.
+#[cfg(feature = "test-utils")]
+#[derive(Clone)]
+pub struct Faults(Rc<FaultState>);
+
+#[cfg(feature = "test-utils")]
+impl Faults {
+    /// Creates [`Faults`] of the room with the provided ID, sharing its
+    /// [`FaultState`] with all the other [`Faults`] of this room.
+    fn new(room_id: RoomId, ws: &Rc<RefCell<WebSocket>>) -> Self {
+        Self(Rc::clone(
+            ws.borrow_mut().faults.entry(room_id).or_default(),
+        ))
+    }
+
+    /// Drops the `n`-th command sent by the room after this call (starting
+    /// from `0`), so it never reaches the server and is never answered.
+    pub fn drop_command(&self, n: usize) {
+        let at = self.0.sent.get() + n;
+        self.0.drop_at.borrow_mut().push(at);
+    }
+
+    /// Delivers the next event sent by server to the room twice with the
+    /// same sequence number.
+    pub fn duplicate_next_event(&self) {
+        self.0.duplicate_next.set(true);
+    }
+
+    /// Closes the connection of the room until [`Faults::reconnect`]: events
+    /// in flight are lost, and all the further commands and events are
+    /// dropped.
+    pub fn close(&self) {
+        self.0.closed.set(true);
+    }
+
+    /// Restores the connection of the room closed via [`Faults::close`].
+    ///
+    /// Events sent before are still lost, so server is expected to deliver
+    /// [`Event::RoomState`] afterwards.
+    pub fn reconnect(&self) {
+        self.0.closed.set(false);
+        self.0.reconnects.set(self.0.reconnects.get() + 1);
     }
 }
@@ -1,29 +1,49 @@
 window.onload = async function() {
     let lib = await import("../../pkg");
     let room = new lib.RoomHandle();
+    try {
+        await room.connect("ws://127.0.0.1:8080/ws", "token");
+    } catch (e) {
+        console.error(`Failed to connect: ${e.message}`);
+    }
+    try {
+        await room.init_local_media(true, true);
+    } catch (e) {
+        console.error(`Failed to init local media: ${e.name}: ${e.message}`);
+    }
 
-    document.getElementById('mute-video').addEventListener('click', async () => {
-        await room.mute(false, true);
-        console.log("Video muted!");
-    });
-    document.getElementById('mute-audio').addEventListener('click', async () => {
-        await room.mute(true, false);
-        console.log("Audio muted!");
-    });
-    document.getElementById('mute-all').addEventListener('click', async () => {
-        await room.mute(true, true);
-        console.log("All muted!");
-    });
-    document.getElementById('unmute-audio').addEventListener('click', async () => {
-        await room.unmute(true, false);
-        console.log("Audio unmuted!");
-    });
-    document.getElementById('unmute-video').addEventListener('click', async () => {
-        await room.unmute(false, true);
-        console.log("Video unmuted!");
-    });
-    document.getElementById('unmute-all').addEventListener('click', async () => {
-        await room.unmute(true, true);
-        console.log("All unmuted!");
-    })
+    let bind = (id, action, message) => {
+        document.getElementById(id).addEventListener('click', async () => {
+            try {
+                await action();
+                console.log(message);
+            } catch (e) {
+                console.error(
+                    `${lib.RoomErrorKind[e.kind]}: ${e.message} ` +
+                    `(audio: ${e.audio}, video: ${e.video})`
+                );
+            }
+        });
+    };
+
+    bind('mute-video', () => room.mute(false, true), "Video muted!");
+    bind('mute-audio', () => room.mute(true, false), "Audio muted!");
+    bind('mute-all', () => room.mute(true, true), "All muted!");
+    bind('unmute-audio', () => room.unmute(true, false), "Audio unmuted!");
+    bind('unmute-video', () => room.unmute(false, true), "Video unmuted!");
+    bind('unmute-all', () => room.unmute(true, true), "All unmuted!");
+    bind('mute-display', () => room.mute_display(), "Display muted!");
+    bind('unmute-display', () => room.unmute_display(), "Display unmuted!");
+    bind(
+        'start-screen-share',
+        async () => room.start_screen_share(),
+        "Screen share started!",
+    );
+    bind(
+        'stop-screen-share',
+        async () => room.stop_screen_share(),
+        "Screen share stopped!",
+    );
+    bind('hold', () => room.hold(), "Room on hold!");
+    bind('resume', () => room.resume(), "Room resumed!");
 };
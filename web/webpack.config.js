@@ -20,6 +20,7 @@ module.exports = {
         }),
         new WasmPackPlugin({
             crateDirectory: path.resolve(__dirname, '../'),
+            extraArgs: '-- --features media',
             // WasmPackPlugin defaults to compiling in "dev" profile.
             // To change that, use `forceMode: 'release'`.
         }),